@@ -0,0 +1,201 @@
+import math
+from datetime import date
+from typing import Dict, Optional
+
+"""
+Agronomic calculations following FAO Irrigation and Drainage Paper 56
+(Allen et al., 1998). Equation numbers refer to that document.
+"""
+
+SOLAR_CONSTANT = 0.0820     # MJ m-2 min-1
+STEFAN_BOLTZMANN = 4.903e-9  # MJ K-4 m-2 day-1
+ALBEDO = 0.23               # Hypothetical grass reference crop
+# Plausible surface conditions; outside them the FAO-56 pressure and vapour pressure
+# formulas break down (negative pressure base above ~45 km, a pole at -237.3 degrees C)
+TEMPERATURE_RANGE = (-90, 60)   # Degrees C
+ELEVATION_RANGE = (-500, 9000)  # Metres
+
+
+def saturation_vapour_pressure(temperature: float) -> float:
+    """Saturation vapour pressure in kPa at a temperature in degrees C (eq. 11)"""
+    return 0.6108 * math.exp(17.27 * temperature / (temperature + 237.3))
+
+
+def extraterrestrial_radiation(latitude: float, day: date) -> float:
+    """Daily extraterrestrial radiation Ra in MJ m-2 day-1 (eq. 21)"""
+    phi = math.radians(latitude)
+    j = day.timetuple().tm_yday
+    dr = 1 + 0.033 * math.cos(2 * math.pi * j / 365)
+    delta = 0.409 * math.sin(2 * math.pi * j / 365 - 1.39)
+    omega = sunset_hour_angle(latitude, day)
+    return (24 * 60 / math.pi) * SOLAR_CONSTANT * dr * (
+        omega * math.sin(phi) * math.sin(delta) +
+        math.cos(phi) * math.cos(delta) * math.sin(omega)
+    )
+
+
+def sunset_hour_angle(latitude: float, day: date) -> float:
+    """Sunset hour angle in radians (eq. 25), clamped for polar day and night"""
+    phi = math.radians(latitude)
+    delta = 0.409 * math.sin(2 * math.pi * day.timetuple().tm_yday / 365 - 1.39)
+    return math.acos(max(-1.0, min(1.0, -math.tan(phi) * math.tan(delta))))
+
+
+def daylight_hours(latitude: float, day: date) -> float:
+    """Maximum possible sunshine duration N in hours (eq. 34)"""
+    return 24 / math.pi * sunset_hour_angle(latitude, day)
+
+
+def wind_speed_2m(wind_speed: float, height: float) -> float:
+    """Adjust wind speed measured at height (m) to the 2 m reference height (eq. 47)"""
+    if height == 2:
+        return wind_speed
+    return wind_speed * 4.87 / math.log(67.8 * height - 5.42)
+
+
+class WeatherError(ValueError):
+    """Invalid weather input; field names the input at fault, or is None for a missing combination"""
+
+    def __init__(self, field: Optional[str], message: str):
+        super().__init__(message)
+        self.field = field
+
+
+def validate_weather(inputs: Dict):
+    """Reject physically impossible daily weather inputs with a message naming the field"""
+    def require(name):
+        if inputs.get(name) is None:
+            raise WeatherError(name, f"{name} is required")
+        if not isinstance(inputs[name], (int, float)) or isinstance(inputs[name], bool):
+            raise WeatherError(name, f"{name} must be a number")
+        # NaN slips through every comparison below and infinity through the open-ended ones
+        if not math.isfinite(inputs[name]):
+            raise WeatherError(name, f"{name} must be a finite number")
+        return inputs[name]
+
+    def within(name, bounds, unit):
+        low, high = bounds
+        if not low <= require(name) <= high:
+            raise WeatherError(name, f"{name} must be between {low} and {high} {unit}")
+
+    within('tmin', TEMPERATURE_RANGE, 'degrees C')
+    within('tmax', TEMPERATURE_RANGE, 'degrees C')
+    tmin, tmax = inputs['tmin'], inputs['tmax']
+    if tmax < tmin:
+        raise WeatherError('tmax', "tmax must not be lower than tmin")
+    if not -90 <= require('latitude') <= 90:
+        raise WeatherError('latitude', "latitude must be between -90 and 90")
+    within('elevation', ELEVATION_RANGE, 'm')
+    if require('wind_speed') < 0:
+        raise WeatherError('wind_speed', "wind_speed must not be negative")
+    if inputs.get('wind_height') is not None and require('wind_height') <= 1:
+        raise WeatherError('wind_height', "wind_height must be above 1 m")
+
+    humidity = [name for name in ('rh_min', 'rh_max', 'rh_mean', 'tdew') if inputs.get(name) is not None]
+    if not humidity:
+        raise WeatherError(None, "one of rh_min/rh_max, rh_mean or tdew is required")
+    if ('rh_min' in humidity) != ('rh_max' in humidity):
+        raise WeatherError(None, "rh_min and rh_max must be given together")
+    for name in ('rh_min', 'rh_max', 'rh_mean'):
+        if name in humidity and not 0 <= require(name) <= 100:
+            raise WeatherError(name, f"{name} must be between 0 and 100")
+    if 'tdew' in humidity:
+        within('tdew', TEMPERATURE_RANGE, 'degrees C')
+    if 'rh_min' in humidity and inputs['rh_min'] > inputs['rh_max']:
+        raise WeatherError('rh_min', "rh_min must not be greater than rh_max")
+
+    if inputs.get('solar_radiation') is None and inputs.get('sunshine_hours') is None:
+        raise WeatherError(None, "one of solar_radiation or sunshine_hours is required")
+    if inputs.get('solar_radiation') is not None and require('solar_radiation') < 0:
+        raise WeatherError('solar_radiation', "solar_radiation must not be negative")
+    if inputs.get('sunshine_hours') is not None and require('sunshine_hours') < 0:
+        raise WeatherError('sunshine_hours', "sunshine_hours must not be negative")
+
+
+def reference_et0(day: date, latitude: float, elevation: float, tmin: float, tmax: float,
+                  wind_speed: float, wind_height: Optional[float] = None,
+                  rh_min: Optional[float] = None, rh_max: Optional[float] = None,
+                  rh_mean: Optional[float] = None, tdew: Optional[float] = None,
+                  solar_radiation: Optional[float] = None,
+                  sunshine_hours: Optional[float] = None) -> Dict:
+    """
+    Daily FAO-56 Penman-Monteith reference evapotranspiration (eq. 6)
+
+    Args:
+        day: Date of the observations
+        latitude: Decimal degrees, positive north
+        elevation: Metres above sea level
+        tmin/tmax: Daily minimum and maximum air temperature (degrees C)
+        wind_speed: Mean daily wind speed (m/s) measured at wind_height metres (default 2)
+        rh_min/rh_max, rh_mean or tdew: Humidity inputs, in that order of preference
+        solar_radiation: Measured Rs (MJ m-2 day-1); otherwise estimated from sunshine_hours
+
+    Returns:
+        Dict with 'et0' in mm/day and the intermediate terms used
+    """
+    if wind_height is None:
+        wind_height = 2.0
+    validate_weather({
+        'latitude': latitude, 'elevation': elevation, 'tmin': tmin, 'tmax': tmax,
+        'wind_speed': wind_speed, 'wind_height': wind_height, 'rh_min': rh_min, 'rh_max': rh_max,
+        'rh_mean': rh_mean, 'tdew': tdew, 'solar_radiation': solar_radiation,
+        'sunshine_hours': sunshine_hours
+    })
+
+    tmean = (tmax + tmin) / 2
+    pressure = 101.3 * ((293 - 0.0065 * elevation) / 293) ** 5.26                  # eq. 7
+    gamma = 0.665e-3 * pressure                                                      # eq. 8
+    slope = 4098 * saturation_vapour_pressure(tmean) / (tmean + 237.3) ** 2          # eq. 13
+
+    es = (saturation_vapour_pressure(tmax) + saturation_vapour_pressure(tmin)) / 2   # eq. 12
+    if rh_min is not None and rh_max is not None:
+        ea = (saturation_vapour_pressure(tmin) * rh_max / 100 +
+              saturation_vapour_pressure(tmax) * rh_min / 100) / 2                   # eq. 17
+    elif rh_mean is not None:
+        ea = rh_mean / 100 * es                                                      # eq. 19
+    else:
+        ea = saturation_vapour_pressure(tdew)                                        # eq. 14
+
+    ra = extraterrestrial_radiation(latitude, day)
+    n_max = daylight_hours(latitude, day)
+    if solar_radiation is None:
+        if sunshine_hours > n_max:
+            raise WeatherError('sunshine_hours', f"sunshine_hours exceeds the {n_max:.1f} h of possible daylight")
+        relative_sunshine = sunshine_hours / n_max if n_max > 0 else 0.0
+        solar_radiation = (0.25 + 0.50 * relative_sunshine) * ra                     # eq. 35
+    rso = (0.75 + 2e-5 * elevation) * ra                                             # eq. 37
+
+    rns = (1 - ALBEDO) * solar_radiation                                             # eq. 38
+    relative_shortwave = min(solar_radiation / rso, 1.0) if rso > 0 else 1.0
+    rnl = STEFAN_BOLTZMANN * ((tmax + 273.16) ** 4 + (tmin + 273.16) ** 4) / 2 * \
+        (0.34 - 0.14 * math.sqrt(ea)) * (1.35 * relative_shortwave - 0.35)           # eq. 39
+    rn = rns - rnl
+
+    u2 = wind_speed_2m(wind_speed, wind_height)
+    # Soil heat flux is negligible at a daily time step (eq. 42)
+    et0 = (0.408 * slope * rn + gamma * 900 / (tmean + 273) * u2 * (es - ea)) / \
+        (slope + gamma * (1 + 0.34 * u2))
+
+    return {
+        'date': day.isoformat(),
+        'et0': max(0.0, et0),
+        'net_radiation': rn,
+        'extraterrestrial_radiation': ra,
+        'solar_radiation': solar_radiation,
+        'vapour_pressure_deficit': es - ea,
+        'wind_speed_2m': u2
+    }
+
+
+def plant_available_water(theta: float, field_capacity: float, wilting_point: float) -> Optional[float]:
+    """
+    Fraction of the available water capacity held at volumetric moisture theta, clamped to [0, 1]
+
+    None when the soil has no available capacity or any input is outside 0..1, as SMAP
+    fill values (-9999) are; clamping one would report a fill value as bone dry.
+    """
+    if not all(0 <= value <= 1 for value in (theta, field_capacity, wilting_point)):
+        return None
+    if field_capacity <= wilting_point:
+        return None
+    return min(1.0, max(0.0, (theta - wilting_point) / (field_capacity - wilting_point)))
@@ -0,0 +1,150 @@
+import os
+import re
+import sqlite3
+import logging
+import argparse
+import time
+from typing import Dict, Optional, Sequence
+
+from moisture import is_valid
+import notify
+
+"""
+Per-location soil moisture threshold alerts. Every published ingestion run is checked
+against the rules for the locations it covers; a rule fires when the location's newest
+valid observation in the run is below (or above) its threshold, then stays quiet for its
+cooldown so a dry week sends one email, not seven. Alerts are emailed through the
+notify outbox, in the same transaction as the rows that triggered them; there are no
+webhook or MQTT channels.
+
+    python3 alerts.py add LOCATION below 0.12 [--cooldown-hours 72] [--to grower@example.com]
+    python3 alerts.py list
+    python3 alerts.py remove ALERT_ID
+"""
+
+logger = logging.getLogger(__name__)
+
+COMPARISONS = ('below', 'above')
+DEFAULT_COOLDOWN_HOURS = 72
+# Loose check; the SMTP server has the final say on an address
+EMAIL = re.compile(r'^[^@\s,]+@[^@\s,]+\.[^@\s,]+$')
+
+
+def create_tables(conn: sqlite3.Connection):
+    conn.execute('''
+        CREATE TABLE IF NOT EXISTS alerts (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            location TEXT,          -- Matches processed_data.location
+            comparison TEXT,        -- below, above
+            threshold REAL,         -- m3/m3
+            recipients TEXT,        -- Comma-separated; OPENFLOW_SMTP_TO when null
+            cooldown_hours REAL,
+            last_notified REAL,     -- Unix time
+            created_at REAL
+        )
+    ''')
+    # Each run only looks up the rules for the locations it covers
+    conn.execute('CREATE INDEX IF NOT EXISTS idx_alerts_location ON alerts (location)')
+
+
+def add(conn: sqlite3.Connection, location: str, comparison: str, threshold: float,
+        cooldown_hours: float = DEFAULT_COOLDOWN_HOURS, recipients: Optional[str] = None) -> int:
+    """Create a rule, raising ValueError if it is invalid"""
+    if not location:
+        raise ValueError("location is required")
+    if comparison not in COMPARISONS:
+        raise ValueError(f"comparison must be one of: {', '.join(COMPARISONS)}")
+    if not 0 <= threshold <= 1:
+        raise ValueError("threshold must be a volumetric fraction between 0 and 1")
+    if cooldown_hours < 0:
+        raise ValueError("cooldown must not be negative")
+    if recipients is not None and not all(EMAIL.match(address.strip()) for address in recipients.split(',')):
+        raise ValueError("recipients must be comma-separated email addresses")
+    create_tables(conn)
+    with conn:
+        return conn.execute('''INSERT INTO alerts (location, comparison, threshold, recipients, cooldown_hours,
+                                                   created_at)
+                               VALUES (?, ?, ?, ?, ?, ?)''',
+                            (location, comparison, threshold, recipients, cooldown_hours, time.time())).lastrowid
+
+
+def remove(conn: sqlite3.Connection, alert_id: int) -> bool:
+    """Delete a rule, returning whether it existed"""
+    create_tables(conn)
+    with conn:
+        return conn.execute('DELETE FROM alerts WHERE id = ?', (alert_id,)).rowcount > 0
+
+
+def _latest(rows: Sequence[tuple]) -> Dict[str, tuple]:
+    """Newest valid (date, smap_value) per location in (date, location, smap_value, vegdri_value) rows"""
+    latest = {}
+    for day, location, value, _ in rows:
+        if is_valid(value) and (location not in latest or day >= latest[location][0]):
+            latest[location] = (day, value)
+    return latest
+
+
+def evaluate(conn: sqlite3.Connection, rows: Sequence[tuple], now: Optional[float] = None) -> int:
+    """Queue an email for every rule the rows trigger outside its cooldown; joins the caller's transaction"""
+    if not notify.configured():
+        return 0
+    create_tables(conn)
+    now = time.time() if now is None else now
+    sent = 0
+    for location, (day, value) in _latest(rows).items():
+        for alert_id, comparison, threshold, recipients, cooldown_hours, last_notified in conn.execute(
+                '''SELECT id, comparison, threshold, recipients, cooldown_hours, last_notified
+                   FROM alerts WHERE location = ?''', (location,)).fetchall():
+            crossed = value < threshold if comparison == 'below' else value > threshold
+            if not crossed or (last_notified is not None and now - last_notified < cooldown_hours * 3600):
+                continue
+            notify.enqueue(conn, 'moisture_alert', to=recipients, alert_id=alert_id, location=location,
+                           date=day, value=f'{value:.3f}', comparison=comparison, threshold=threshold,
+                           cooldown_hours=f'{cooldown_hours:g}')
+            conn.execute('UPDATE alerts SET last_notified = ? WHERE id = ?', (now, alert_id))
+            sent += 1
+    if sent:
+        logger.info(f"Queued {sent} moisture alerts")
+    return sent
+
+
+def main():
+    logging.basicConfig(level=logging.INFO)
+    parser = argparse.ArgumentParser(description="Manage soil moisture threshold alerts")
+    parser.add_argument('--db', default=os.getenv('OPENFLOW_DB_PATH', '/var/lib/openflow/data.db'),
+                        help="API database path (default OPENFLOW_DB_PATH)")
+    actions = parser.add_subparsers(dest='action', required=True)
+    add_action = actions.add_parser('add')
+    add_action.add_argument('location', help="processed_data location")
+    add_action.add_argument('comparison', choices=COMPARISONS)
+    add_action.add_argument('threshold', type=float, help="m3/m3")
+    add_action.add_argument('--cooldown-hours', type=float, default=DEFAULT_COOLDOWN_HOURS,
+                            help="Minimum time between emails for this rule")
+    add_action.add_argument('--to', help="Comma-separated recipients (default OPENFLOW_SMTP_TO)")
+    actions.add_parser('list')
+    actions.add_parser('remove').add_argument('alert_id', type=int)
+    args = parser.parse_args()
+
+    conn = sqlite3.connect(args.db)
+    try:
+        create_tables(conn)
+        if args.action == 'add':
+            try:
+                alert_id = add(conn, args.location, args.comparison, args.threshold, args.cooldown_hours, args.to)
+            except ValueError as e:
+                parser.error(str(e))
+            logger.info(f"Added alert {alert_id}")
+            if not notify.configured():
+                logger.warning("Email is not configured; alerts won't fire until OPENFLOW_SMTP_* are set")
+        elif args.action == 'list':
+            for row in conn.execute("SELECT id, location, comparison, threshold, recipients, cooldown_hours, "
+                                    "datetime(last_notified, 'unixepoch') FROM alerts ORDER BY id"):
+                print(*row, sep='\t')
+        elif not remove(conn, args.alert_id):
+            parser.error(f"no alert {args.alert_id}")
+    finally:
+        conn.close()
+
+
+if __name__ == "__main__":
+    main()
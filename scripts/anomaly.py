@@ -0,0 +1,189 @@
+import os
+import sqlite3
+import logging
+import argparse
+from datetime import datetime, timezone
+from statistics import mean
+from typing import Dict, List, Sequence, Tuple
+
+import alerts
+import integrity
+from moisture import is_valid
+import notify
+
+"""
+Ingestion anomaly checks. Each run's summary statistics are compared with the trailing
+accepted runs; a run that deviates is quarantined: its rows are held in
+quarantined_data instead of processed_data, so no API route serves them, until an
+operator approves (publishes) or rolls back (discards) it:
+
+    python3 anomaly.py list
+    python3 anomaly.py approve RUN_ID
+    python3 anomaly.py rollback RUN_ID
+"""
+
+logger = logging.getLogger(__name__)
+
+HISTORY_RUNS = int(os.getenv('OPENFLOW_ANOMALY_HISTORY', '10'))
+# Fewer accepted runs than this and there is no baseline to judge against
+MIN_HISTORY_RUNS = 3
+MAX_MEAN_SHIFT = float(os.getenv('OPENFLOW_ANOMALY_MEAN_SHIFT', '0.1'))  # m3/m3
+MAX_VALID_DROP = float(os.getenv('OPENFLOW_ANOMALY_VALID_DROP', '0.3'))
+MAX_COVERAGE_DROP = float(os.getenv('OPENFLOW_ANOMALY_COVERAGE_DROP', '0.5'))
+# Statuses whose runs are published and count towards the baseline
+PUBLISHED = ('accepted', 'approved')
+
+
+def create_tables(conn: sqlite3.Connection):
+    conn.execute('''
+        CREATE TABLE IF NOT EXISTS ingestion_runs (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            finished_at TEXT,
+            row_count INTEGER,
+            mean REAL,              -- Of valid smap_value observations, m3/m3
+            valid_fraction REAL,
+            locations INTEGER,      -- Distinct locations with a valid observation
+            status TEXT,            -- accepted, quarantined, approved, rolled_back
+            reason TEXT
+        )
+    ''')
+    conn.execute('''
+        CREATE TABLE IF NOT EXISTS quarantined_data (
+            run_id INTEGER,
+            date TEXT,
+            location TEXT,
+            smap_value REAL,
+            vegdri_value REAL
+        )
+    ''')
+
+
+def summarize(rows: Sequence[tuple]) -> Dict:
+    """Statistics of (date, location, smap_value, vegdri_value) rows; fill values aren't valid"""
+    valid = [(location, value) for _, location, value, _ in rows if is_valid(value)]
+    return {
+        'row_count': len(rows),
+        'mean': mean(value for _, value in valid) if valid else None,
+        'valid_fraction': len(valid) / len(rows) if rows else 0.0,
+        'locations': len({location for location, _ in valid})
+    }
+
+
+def deviations(summary: Dict, history: List[Dict]) -> List[str]:
+    """Reasons a run's summary is out of line with earlier runs; empty when it looks normal"""
+    if len(history) < MIN_HISTORY_RUNS:
+        return []
+    reasons = []
+    means = [run['mean'] for run in history if run['mean'] is not None]
+    if summary['mean'] is not None and means and abs(summary['mean'] - mean(means)) > MAX_MEAN_SHIFT:
+        reasons.append(f"mean {summary['mean']:.3f} is more than {MAX_MEAN_SHIFT} from trailing {mean(means):.3f}")
+    valid = mean(run['valid_fraction'] for run in history)
+    if valid - summary['valid_fraction'] > MAX_VALID_DROP:
+        reasons.append(f"valid fraction {summary['valid_fraction']:.2f} dropped from trailing {valid:.2f}")
+    locations = mean(run['locations'] for run in history)
+    if summary['locations'] < locations * (1 - MAX_COVERAGE_DROP):
+        reasons.append(f"{summary['locations']} locations covered against trailing {locations:.1f}")
+    return reasons
+
+
+def _history(conn: sqlite3.Connection) -> List[Dict]:
+    conn.row_factory = sqlite3.Row
+    try:
+        return [dict(row) for row in conn.execute(
+            f'''SELECT mean, valid_fraction, locations FROM ingestion_runs
+                WHERE status IN ({', '.join('?' * len(PUBLISHED))}) ORDER BY id DESC LIMIT ?''',
+            (*PUBLISHED, HISTORY_RUNS))]
+    finally:
+        conn.row_factory = None
+
+
+def ingest(conn: sqlite3.Connection, rows: Sequence[tuple]) -> Tuple[int, str, List[str]]:
+    """Record a run and publish its rows, or hold them if the run looks anomalous"""
+    create_tables(conn)
+    summary = summarize(rows)
+    reasons = deviations(summary, _history(conn))
+    status = 'quarantined' if reasons else 'accepted'
+    with conn:
+        run_id = conn.execute('''
+            INSERT INTO ingestion_runs (finished_at, row_count, mean, valid_fraction, locations, status, reason)
+            VALUES (?, ?, ?, ?, ?, ?, ?)
+        ''', (datetime.now(timezone.utc).isoformat(), summary['row_count'], summary['mean'],
+              summary['valid_fraction'], summary['locations'], status, '; '.join(reasons) or None)).lastrowid
+        if reasons and notify.configured():
+            # Commits or rolls back with the run, so a crash can't lose the alert
+            notify.enqueue(conn, 'ingestion_quarantined', run_id=run_id, reasons='\n'.join(reasons),
+                           rows=len(rows))
+        if reasons:
+            conn.executemany('INSERT INTO quarantined_data VALUES (?, ?, ?, ?, ?)',
+                             [(run_id, *row) for row in rows])
+        else:
+            conn.executemany('''INSERT INTO processed_data (date, location, smap_value, vegdri_value)
+                                VALUES (?, ?, ?, ?)''', rows)
+            integrity.record(conn, (row[0] for row in rows))
+            alerts.evaluate(conn, rows)
+    if reasons:
+        logger.warning(f"Quarantined ingestion run {run_id}: {'; '.join(reasons)}")
+    return run_id, status, reasons
+
+
+def _quarantined(conn: sqlite3.Connection, run_id: int):
+    row = conn.execute('SELECT status FROM ingestion_runs WHERE id = ?', (run_id,)).fetchone()
+    if row is None or row[0] != 'quarantined':
+        raise ValueError(f"run {run_id} is not quarantined")
+
+
+def approve(conn: sqlite3.Connection, run_id: int) -> int:
+    """Publish a quarantined run's rows, returning how many moved"""
+    with conn:
+        _quarantined(conn, run_id)
+        moved = conn.execute('''INSERT INTO processed_data (date, location, smap_value, vegdri_value)
+                                SELECT date, location, smap_value, vegdri_value FROM quarantined_data
+                                WHERE run_id = ?''', (run_id,)).rowcount
+        integrity.record(conn, (row[0] for row in conn.execute(
+            'SELECT DISTINCT date FROM quarantined_data WHERE run_id = ?', (run_id,))))
+        alerts.evaluate(conn, conn.execute('''SELECT date, location, smap_value, vegdri_value FROM quarantined_data
+                                              WHERE run_id = ?''', (run_id,)).fetchall())
+        conn.execute('DELETE FROM quarantined_data WHERE run_id = ?', (run_id,))
+        conn.execute("UPDATE ingestion_runs SET status = 'approved' WHERE id = ?", (run_id,))
+    return moved
+
+
+def rollback(conn: sqlite3.Connection, run_id: int) -> int:
+    """Discard a quarantined run's rows, returning how many were dropped"""
+    with conn:
+        _quarantined(conn, run_id)
+        dropped = conn.execute('DELETE FROM quarantined_data WHERE run_id = ?', (run_id,)).rowcount
+        conn.execute("UPDATE ingestion_runs SET status = 'rolled_back' WHERE id = ?", (run_id,))
+    return dropped
+
+
+def main():
+    logging.basicConfig(level=logging.INFO)
+    parser = argparse.ArgumentParser(description="Review quarantined ingestion runs")
+    parser.add_argument('action', choices=('list', 'approve', 'rollback'))
+    parser.add_argument('run_id', nargs='?', type=int)
+    parser.add_argument('--db', default=os.getenv('OPENFLOW_DB_PATH', '/var/lib/openflow/data.db'),
+                        help="API database path (default OPENFLOW_DB_PATH)")
+    args = parser.parse_args()
+    if args.action != 'list' and args.run_id is None:
+        parser.error(f"{args.action} needs a run id")
+
+    conn = sqlite3.connect(args.db)
+    try:
+        create_tables(conn)
+        if args.action == 'list':
+            for run in conn.execute("SELECT id, finished_at, row_count, mean, reason FROM ingestion_runs "
+                                    "WHERE status = 'quarantined' ORDER BY id"):
+                print(*run, sep='\t')
+        else:
+            try:
+                count = (approve if args.action == 'approve' else rollback)(conn, args.run_id)
+            except ValueError as e:
+                parser.error(str(e))
+            logger.info(f"{'Published' if args.action == 'approve' else 'Discarded'} {count} rows from run {args.run_id}")
+    finally:
+        conn.close()
+
+
+if __name__ == "__main__":
+    main()
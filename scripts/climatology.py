@@ -0,0 +1,194 @@
+import os
+import calendar
+import sqlite3
+import logging
+import argparse
+import time
+from datetime import date
+from typing import Dict, List, Optional
+
+from moisture import VALID_MOISTURE
+
+"""
+Precompute per-location, per-day-of-year soil moisture baselines from every stored
+year in processed_data. Each day of year pools observations within WINDOW_DAYS either
+side, wrapping across the new year. Run weekly from cron; only locations whose data
+changed since the last run are recomputed unless --full is given.
+"""
+
+logger = logging.getLogger(__name__)
+
+WINDOW_DAYS = 7
+# Dense enough at the dry end to interpolate drought percentiles; 0 and 100 are min and max
+QUANTILES = (0, 2, 5, 10, 20, 25, 30, 50, 70, 75, 80, 90, 95, 98, 100)
+MIN_OBSERVATIONS = 10
+
+
+def create_tables(conn: sqlite3.Connection):
+    """Create the climatology tables if they don't exist"""
+    conn.execute(f'''
+        CREATE TABLE IF NOT EXISTS climatology (
+            location TEXT,
+            day_of_year INTEGER,     -- 1-365, leap days share Feb 28's index
+            mean REAL,
+            stddev REAL,
+            {', '.join(f'p{q} REAL' for q in QUANTILES)},
+            observations INTEGER,
+            years INTEGER,
+            computed_at INTEGER,     -- Unix time
+            PRIMARY KEY (location, day_of_year)
+        )
+    ''')
+    # What each location's baselines were computed from, to detect new data
+    conn.execute('''
+        CREATE TABLE IF NOT EXISTS climatology_sources (
+            location TEXT PRIMARY KEY,
+            observations INTEGER,
+            latest_date TEXT,
+            computed_at INTEGER
+        )
+    ''')
+
+    # Quantiles added since the table was created: add the columns and recompute everything
+    existing = {row[1] for row in conn.execute("PRAGMA table_info(climatology)")}
+    missing = [f'p{q}' for q in QUANTILES if f'p{q}' not in existing]
+    for column in missing:
+        conn.execute(f"ALTER TABLE climatology ADD COLUMN {column} REAL")
+    if missing:
+        conn.execute("DELETE FROM climatology_sources")
+        conn.commit()
+
+
+def day_of_year(day: date) -> int:
+    """Day of year on a 365-day calendar so the same date lines up across leap years"""
+    doy = day.timetuple().tm_yday
+    # Feb 29 shares Feb 28's index and the rest of a leap year shifts back by one
+    if calendar.isleap(day.year) and (day.month > 2 or day.day == 29):
+        doy -= 1
+    return doy
+
+
+def quantile(ordered: List[float], percent: float) -> float:
+    """Linearly interpolated percentile of an already sorted list"""
+    position = (len(ordered) - 1) * percent / 100
+    lower = int(position)
+    upper = min(lower + 1, len(ordered) - 1)
+    return ordered[lower] + (ordered[upper] - ordered[lower]) * (position - lower)
+
+
+def percentile_of(value: float, quantiles: Dict[int, float]) -> Optional[float]:
+    """Invert stored quantiles {percent: value} by linear interpolation, None if any are missing"""
+    points = sorted(quantiles.items())
+    if any(v is None for _, v in points):
+        return None
+    # A value matching a flat stretch of the distribution sits in the middle of it
+    tied = [p for p, v in points if v == value]
+    if tied:
+        return (tied[0] + tied[-1]) / 2
+    if value < points[0][1]:
+        return float(points[0][0])
+    for (p_low, v_low), (p_high, v_high) in zip(points, points[1:]):
+        if value < v_high:
+            return p_low + (p_high - p_low) * (value - v_low) / (v_high - v_low)
+    return float(points[-1][0])
+
+
+def baselines(observations: Dict[date, float], window: int = WINDOW_DAYS,
+              min_observations: int = MIN_OBSERVATIONS) -> Dict[int, Dict]:
+    """Statistics for every day of year with at least min_observations in its window"""
+    by_doy = {}
+    for day, value in observations.items():
+        by_doy.setdefault(day_of_year(day), []).append((day.year, value))
+
+    stats = {}
+    for doy in range(1, 366):
+        pooled = []
+        for offset in range(-window, window + 1):
+            pooled.extend(by_doy.get((doy + offset - 1) % 365 + 1, []))
+        if len(pooled) < min_observations:
+            continue
+
+        values = sorted(value for _, value in pooled)
+        mean = sum(values) / len(values)
+        stats[doy] = {
+            'mean': mean,
+            'stddev': (sum((v - mean) ** 2 for v in values) / (len(values) - 1)) ** 0.5,
+            **{f'p{q}': quantile(values, q) for q in QUANTILES},
+            'observations': len(values),
+            'years': len({year for year, _ in pooled})
+        }
+    return stats
+
+
+def stale_locations(conn: sqlite3.Connection, full: bool = False) -> List[str]:
+    """Locations whose stored observations differ from what their baselines were built from"""
+    current = conn.execute(f'''
+        SELECT location, COUNT(*), MAX(date) FROM processed_data
+        WHERE {VALID_MOISTURE} GROUP BY location ORDER BY location
+    ''').fetchall()
+    if full:
+        return [location for location, _, _ in current]
+    sources = {location: (count, latest) for location, count, latest in conn.execute(
+        'SELECT location, observations, latest_date FROM climatology_sources')}
+    return [location for location, count, latest in current if sources.get(location) != (count, latest)]
+
+
+def compute_location(conn: sqlite3.Connection, location: str, computed_at: Optional[int] = None) -> int:
+    """Replace one location's baselines in a single transaction, returning the days stored"""
+    computed_at = computed_at or int(time.time())
+    rows = conn.execute(f'''
+        SELECT date, smap_value FROM processed_data
+        WHERE location = ? AND {VALID_MOISTURE}
+    ''', (location,)).fetchall()
+    observations = {date.fromisoformat(day): value for day, value in rows}
+    stats = baselines(observations)
+
+    columns = ['mean', 'stddev'] + [f'p{q}' for q in QUANTILES] + ['observations', 'years']
+    with conn:
+        conn.execute('DELETE FROM climatology WHERE location = ?', (location,))
+        conn.executemany(f'''
+            INSERT INTO climatology (location, day_of_year, {', '.join(columns)}, computed_at)
+            VALUES ({', '.join('?' * (len(columns) + 3))})
+        ''', [(location, doy, *(s[c] for c in columns), computed_at) for doy, s in stats.items()])
+        conn.execute('''
+            INSERT OR REPLACE INTO climatology_sources (location, observations, latest_date, computed_at)
+            VALUES (?, ?, ?, ?)
+        ''', (location, len(rows), max((day for day, _ in rows), default=None), computed_at))
+    return len(stats)
+
+
+def update(conn: sqlite3.Connection, full: bool = False) -> int:
+    """
+    Recompute baselines for changed locations, returning how many were recomputed
+
+    Each location commits on its own, so an interrupted run keeps finished locations
+    and the next run picks up the rest.
+    """
+    create_tables(conn)
+    locations = stale_locations(conn, full)
+    logger.info(f"Computing climatology for {len(locations)} locations")
+    for i, location in enumerate(locations, start=1):
+        days = compute_location(conn, location)
+        logger.info(f"[{i}/{len(locations)}] {location}: {days} days of year")
+    return len(locations)
+
+
+def main():
+    logging.basicConfig(level=logging.INFO)
+    parser = argparse.ArgumentParser(description="Precompute day-of-year soil moisture climatology")
+    parser.add_argument('--db', default=os.getenv('OPENFLOW_DB_PATH', '/var/lib/openflow/data.db'),
+                        help="API database path (default OPENFLOW_DB_PATH)")
+    parser.add_argument('--full', action='store_true', help="Recompute every location")
+    args = parser.parse_args()
+
+    conn = sqlite3.connect(args.db)
+    try:
+        update(conn, args.full)
+    except KeyboardInterrupt:
+        logger.warning("Interrupted; completed locations are kept and the rest resume next run")
+    finally:
+        conn.close()
+
+
+if __name__ == "__main__":
+    main()
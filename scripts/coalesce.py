@@ -0,0 +1,54 @@
+import threading
+from typing import Any, Callable, Dict, Hashable
+
+"""
+Single-flight request coalescing: concurrent callers with the same key share one
+computation. The first caller (the leader) runs it; callers arriving while it is in
+flight wait and receive the leader's result, or its exception. A key is forgotten as
+soon as its computation finishes, so a failure is never served to later callers.
+"""
+
+
+class _Flight:
+    def __init__(self):
+        self.done = threading.Event()
+        self.result = None
+        self.error = None
+
+
+class SingleFlight:
+    """Coalesces concurrent calls per key and counts how many were shared"""
+
+    def __init__(self):
+        self._flights: Dict[Hashable, _Flight] = {}
+        self._lock = threading.Lock()
+        self.leaders = 0
+        self.coalesced = 0
+
+    def do(self, key: Hashable, fn: Callable[[], Any]) -> Any:
+        with self._lock:
+            flight = self._flights.get(key)
+            leader = flight is None
+            if leader:
+                flight = self._flights[key] = _Flight()
+                self.leaders += 1
+            else:
+                self.coalesced += 1
+
+        if not leader:
+            flight.done.wait()
+            if flight.error is not None:
+                raise flight.error
+            return flight.result
+
+        try:
+            flight.result = fn()
+            return flight.result
+        except BaseException as e:
+            flight.error = e
+            raise
+        finally:
+            # Forget the key before waking followers so the next caller starts fresh
+            with self._lock:
+                del self._flights[key]
+            flight.done.set()
@@ -0,0 +1,150 @@
+import os
+import csv
+import sqlite3
+import logging
+import argparse
+from typing import Dict, Iterable, List, Optional, Tuple
+
+"""
+Per-crop optimal moisture bands for the API's classification option. Each band is a
+plant-available water range, used where a location has soil properties, plus a
+volumetric fallback used everywhere else. Presets put the lower PAW bound at the
+FAO-56 (Table 22) readily available water limit, 1 - p, and the upper bound at field
+capacity; their volumetric bands apply the same fractions to a reference loam.
+
+Edit bands with a CSV (header row crop,stage,paw_lower,paw_upper,vwc_lower,vwc_upper):
+
+    python3 crops.py --db /var/lib/openflow/data.db bands.csv
+"""
+
+logger = logging.getLogger(__name__)
+
+DEFAULT_STAGE = 'season'
+# Reference loam (FAO-56 Table 19 midpoints), m3/m3
+REFERENCE_FIELD_CAPACITY = 0.25
+REFERENCE_WILTING_POINT = 0.12
+# FAO-56 Table 22 soil water depletion fraction for no stress, p
+DEPLETION_FRACTIONS = {
+    'corn': 0.55,
+    'wheat': 0.55,
+    'alfalfa': 0.55,
+    'soybean': 0.50,
+    'tomato': 0.40,
+    'potato': 0.35
+}
+BAND_COLUMNS = ('paw_lower', 'paw_upper', 'vwc_lower', 'vwc_upper')
+
+
+def _preset(p: float) -> Tuple[float, float, float, float]:
+    span = REFERENCE_FIELD_CAPACITY - REFERENCE_WILTING_POINT
+    lower = round(1 - p, 4)
+    return lower, 1.0, round(REFERENCE_WILTING_POINT + lower * span, 4), REFERENCE_FIELD_CAPACITY
+
+
+PRESETS = {(crop, DEFAULT_STAGE): _preset(p) for crop, p in DEPLETION_FRACTIONS.items()}
+
+
+def create_table(conn: sqlite3.Connection) -> int:
+    """Create the crops table if needed and add any missing presets without touching edited rows, returning how many"""
+    conn.execute('''
+        CREATE TABLE IF NOT EXISTS crops (
+            crop TEXT,
+            stage TEXT,
+            paw_lower REAL,     -- Fraction of available water capacity
+            paw_upper REAL,
+            vwc_lower REAL,     -- m3/m3, when the location has no soil properties
+            vwc_upper REAL,
+            PRIMARY KEY (crop, stage)
+        )
+    ''')
+    with conn:
+        added = conn.executemany('INSERT OR IGNORE INTO crops VALUES (?, ?, ?, ?, ?, ?)',
+                                 [(crop, stage, *band) for (crop, stage), band in PRESETS.items()]).rowcount
+    return added
+
+
+def parse_rows(rows: Iterable[Dict]) -> List[tuple]:
+    """Validate CSV rows, raising ValueError naming the line and column at fault"""
+    parsed = []
+    # Line 1 is the header
+    for line, row in enumerate(rows, start=2):
+        crop = (row.get('crop') or '').strip().lower()
+        if not crop:
+            raise ValueError(f"line {line}: crop is required")
+        stage = (row.get('stage') or '').strip().lower() or DEFAULT_STAGE
+        values = {}
+        for column in BAND_COLUMNS:
+            try:
+                values[column] = float(row[column])
+            except (KeyError, TypeError, ValueError):
+                raise ValueError(f"line {line}: {column} must be a number")
+            if not 0 <= values[column] <= 1:
+                raise ValueError(f"line {line}: {column} must be between 0 and 1")
+        for basis in ('paw', 'vwc'):
+            if values[f'{basis}_lower'] >= values[f'{basis}_upper']:
+                raise ValueError(f"line {line}: {basis}_lower must be below {basis}_upper")
+        parsed.append((crop, stage, *(values[column] for column in BAND_COLUMNS)))
+    return parsed
+
+
+def load_bands(conn: sqlite3.Connection, rows: Iterable[Dict]) -> int:
+    """Validate and upsert bands; nothing is written if any row is invalid"""
+    parsed = parse_rows(rows)
+    create_table(conn)
+    with conn:
+        conn.executemany('INSERT OR REPLACE INTO crops VALUES (?, ?, ?, ?, ?, ?)', parsed)
+    return len(parsed)
+
+
+def available(conn: sqlite3.Connection) -> List[str]:
+    """Every configured crop/stage as 'crop' or 'crop:stage' for non-default stages"""
+    try:
+        rows = conn.execute('SELECT crop, stage FROM crops ORDER BY crop, stage').fetchall()
+    except sqlite3.OperationalError:
+        # crops.py has never been run against this database
+        return []
+    return [crop if stage == DEFAULT_STAGE else f'{crop}:{stage}' for crop, stage in rows]
+
+
+def lookup(conn: sqlite3.Connection, crop: str, stage: str = DEFAULT_STAGE) -> Optional[Dict[str, float]]:
+    """A crop's band, or None if it isn't configured"""
+    try:
+        row = conn.execute(f"SELECT {', '.join(BAND_COLUMNS)} FROM crops WHERE crop = ? AND stage = ?",
+                           (crop.lower(), stage.lower())).fetchone()
+    except sqlite3.OperationalError:
+        return None
+    return dict(zip(BAND_COLUMNS, row)) if row else None
+
+
+def classify(value: float, lower: float, upper: float) -> str:
+    """Place a value below, within, or above an inclusive band"""
+    if value < lower:
+        return 'below'
+    return 'above' if value > upper else 'within'
+
+
+def main():
+    logging.basicConfig(level=logging.INFO)
+    parser = argparse.ArgumentParser(description="Install crop moisture band presets and apply edits")
+    parser.add_argument('csv_file', nargs='?', help="CSV with crop,stage,paw_lower,paw_upper,vwc_lower,vwc_upper")
+    parser.add_argument('--db', default=os.getenv('OPENFLOW_DB_PATH', '/var/lib/openflow/data.db'),
+                        help="API database path (default OPENFLOW_DB_PATH)")
+    args = parser.parse_args()
+
+    conn = sqlite3.connect(args.db)
+    try:
+        added = create_table(conn)
+        if added:
+            # The deploy playbook reports a change only when this line is logged
+            logger.info(f"Installed {added} crop presets")
+        if args.csv_file:
+            with open(args.csv_file, newline='') as f:
+                count = load_bands(conn, list(csv.DictReader(f)))
+            logger.info(f"Imported {count} crop bands into {args.db}")
+        logger.info(f"Configured crops: {', '.join(available(conn))}")
+    finally:
+        conn.close()
+
+
+if __name__ == "__main__":
+    main()
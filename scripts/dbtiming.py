@@ -0,0 +1,127 @@
+import os
+import sqlite3
+import logging
+import time
+from typing import Optional
+
+"""
+Instrumented SQLite connections: every statement is timed from execute through its
+last fetch and logged when slow, and a connection can carry a wall-clock budget that
+interrupts whatever statement is running once it is spent.
+"""
+
+logger = logging.getLogger(__name__)
+
+SLOW_QUERY_MS = float(os.getenv('OPENFLOW_SLOW_QUERY_MS', '500'))
+REQUEST_BUDGET_MS = float(os.getenv('OPENFLOW_DB_BUDGET_MS', '5000'))
+# SQLite virtual machine instructions between budget checks
+PROGRESS_INTERVAL = 1000
+
+
+class QueryBudgetExceeded(Exception):
+    """A connection's database time budget ran out mid-statement"""
+
+
+def _loggable(params) -> tuple:
+    """Query parameters for the log, with floats (coordinates) rounded for privacy"""
+    if isinstance(params, dict):
+        params = tuple(params.values())
+    return tuple(round(p, 2) if isinstance(p, float) else p for p in params)
+
+
+class TimedCursor(sqlite3.Cursor):
+    """Cursor that accumulates time and rows per statement and logs slow ones"""
+    _statement = None
+
+    def execute(self, sql, parameters=()):
+        self._finish()
+        self._statement, self._elapsed, self._rows = (sql, parameters), 0.0, 0
+        self._timed(super().execute, sql, parameters)
+        return self
+
+    def fetchone(self):
+        row = self._timed(super().fetchone)
+        if row is None:
+            self._finish()
+        else:
+            self._rows += 1
+        return row
+
+    def fetchall(self):
+        rows = self._timed(super().fetchall)
+        self._rows += len(rows)
+        self._finish()
+        return rows
+
+    def __next__(self):
+        try:
+            row = self._timed(super().__next__)
+        except StopIteration:
+            self._finish()
+            raise
+        self._rows += 1
+        return row
+
+    def close(self):
+        self._finish()
+        super().close()
+
+    def __del__(self):
+        # Single-row lookups like conn.execute(...).fetchone() are never fetched to exhaustion
+        self._finish()
+
+    def _timed(self, method, *args):
+        started = time.perf_counter()
+        try:
+            return method(*args)
+        except sqlite3.OperationalError:
+            if self.connection.budget_exceeded:
+                raise QueryBudgetExceeded(f"database time budget of {self.connection.budget_ms:.0f} ms exceeded") from None
+            raise
+        finally:
+            self._elapsed += time.perf_counter() - started
+
+    def _finish(self):
+        if self._statement is None:
+            return
+        (sql, params), self._statement = self._statement, None
+        elapsed_ms = self._elapsed * 1000
+        if elapsed_ms >= self.connection.slow_query_ms:
+            logger.warning(f"Slow query ({elapsed_ms:.0f} ms, {self._rows} rows): "
+                           f"{' '.join(sql.split())} params={_loggable(params)}")
+
+
+class TimedConnection(sqlite3.Connection):
+    """Connection whose cursors are all TimedCursors"""
+    budget_exceeded = False
+    budget_ms: Optional[float] = None
+    slow_query_ms = SLOW_QUERY_MS
+
+    def cursor(self, factory=TimedCursor):
+        return super().cursor(factory)
+
+    def execute(self, sql, parameters=()):
+        # sqlite3.Connection.execute would otherwise bypass cursor()
+        return self.cursor().execute(sql, parameters)
+
+    def start_budget(self, budget_ms: Optional[float]):
+        """Interrupt statements once budget_ms of wall-clock time has passed; None or 0 disables"""
+        self.budget_ms = budget_ms
+        if not budget_ms:
+            self.set_progress_handler(None, 0)
+            return
+        deadline = time.monotonic() + budget_ms / 1000
+
+        def check():
+            if time.monotonic() > deadline:
+                self.budget_exceeded = True
+                return 1
+            return 0
+        self.set_progress_handler(check, PROGRESS_INTERVAL)
+
+
+def connect(path, budget_ms: Optional[float] = REQUEST_BUDGET_MS, **kwargs) -> TimedConnection:
+    """Open a timed connection whose budget starts now"""
+    conn = sqlite3.connect(path, factory=TimedConnection, **kwargs)
+    conn.start_budget(budget_ms)
+    return conn
@@ -0,0 +1,119 @@
+import os
+import getpass
+import sqlite3
+import logging
+import argparse
+from datetime import date, datetime, timezone
+from typing import Dict, Optional
+
+import climatology
+import integrity
+
+"""
+Remove every processed_data row for a date, e.g. after ingesting a corrupted granule,
+instead of hand-written SQL:
+
+    python3 delete_date.py 2024-05-03 [--dry-run] [--reason "corrupt granule"]
+
+Rows are deleted in batches, each its own transaction, so a large date doesn't hold the
+write lock; rerunning an interrupted deletion finishes it. The date's integrity checksum
+is dropped and affected locations' climatology recomputed, and each deletion is recorded
+in the deletions table with who ran it and why. --dry-run only reports the row and
+location counts. A date with no stored rows exits with status 2 rather than reporting
+zero, so a mistyped date isn't mistaken for a deletion. Granules aren't tracked, so
+there is no per-granule deletion.
+"""
+
+logger = logging.getLogger(__name__)
+
+BATCH_ROWS = 5000
+
+
+class NoSuchDate(LookupError):
+    """No stored rows for the date"""
+
+
+def create_tables(conn: sqlite3.Connection):
+    conn.execute('''
+        CREATE TABLE IF NOT EXISTS deletions (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            date TEXT,
+            row_count INTEGER,
+            locations INTEGER,
+            deleted_at TEXT,
+            operator TEXT,          -- OS user that ran the deletion
+            reason TEXT
+        )
+    ''')
+
+
+def preview(conn: sqlite3.Connection, day: str) -> Dict:
+    """What deleting a date would remove, raising NoSuchDate when nothing is stored for it"""
+    rows, locations = conn.execute('SELECT COUNT(*), COUNT(DISTINCT location) FROM processed_data WHERE date = ?',
+                                   (day,)).fetchone()
+    if not rows:
+        raise NoSuchDate(f"no rows stored for {day}")
+    return {'date': day, 'rows': rows, 'locations': locations}
+
+
+def delete_date(conn: sqlite3.Connection, day: str, reason: Optional[str] = None,
+                batch_rows: int = BATCH_ROWS) -> int:
+    """Delete a date's rows and update what was derived from them, returning the rows deleted"""
+    summary = preview(conn, day)
+    create_tables(conn)
+    integrity.create_tables(conn)
+    climatology.create_tables(conn)
+    affected = [row[0] for row in conn.execute(
+        'SELECT DISTINCT location FROM processed_data WHERE date = ? AND location IN '
+        '(SELECT location FROM climatology_sources)', (day,))]
+
+    deleted = 0
+    while True:
+        with conn:
+            count = conn.execute('''DELETE FROM processed_data WHERE rowid IN
+                                    (SELECT rowid FROM processed_data WHERE date = ? LIMIT ?)''',
+                                 (day, batch_rows)).rowcount
+        deleted += count
+        if count < batch_rows:
+            break
+
+    with conn:
+        # Nothing is left to verify, so the date shouldn't turn up as suspect
+        conn.execute('DELETE FROM date_checksums WHERE date = ?', (day,))
+        conn.execute('''INSERT INTO deletions (date, row_count, locations, deleted_at, operator, reason)
+                        VALUES (?, ?, ?, ?, ?, ?)''',
+                     (day, deleted, summary['locations'], datetime.now(timezone.utc).isoformat(),
+                      getpass.getuser(), reason))
+    # Only locations that already have baselines; the rest get theirs on the next weekly run
+    for location in affected:
+        climatology.compute_location(conn, location)
+    logger.info(f"Deleted {deleted} rows for {day}, recomputed climatology for {len(affected)} locations")
+    return deleted
+
+
+def main():
+    logging.basicConfig(level=logging.INFO)
+    parser = argparse.ArgumentParser(description="Delete all stored soil moisture rows for a date")
+    parser.add_argument('date', type=date.fromisoformat, help="YYYY-MM-DD")
+    parser.add_argument('--dry-run', action='store_true', help="Report what would be deleted")
+    parser.add_argument('--reason', help="Recorded with the deletion")
+    parser.add_argument('--db', default=os.getenv('OPENFLOW_DB_PATH', '/var/lib/openflow/data.db'),
+                        help="API database path (default OPENFLOW_DB_PATH)")
+    args = parser.parse_args()
+
+    conn = sqlite3.connect(args.db)
+    try:
+        if args.dry_run:
+            summary = preview(conn, args.date.isoformat())
+            print(f"Would delete {summary['rows']} rows across {summary['locations']} locations for {summary['date']}")
+        else:
+            print(delete_date(conn, args.date.isoformat(), args.reason))
+    except NoSuchDate as e:
+        logger.error(str(e))
+        raise SystemExit(2)
+    finally:
+        conn.close()
+
+
+if __name__ == "__main__":
+    main()
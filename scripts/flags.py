@@ -0,0 +1,62 @@
+import enum
+from typing import Dict, Iterable, Optional
+
+"""
+SMAP L3 surface_flag bitfield (SPL3SMP/SPL3SMP_E product specification). A set bit
+means the condition was detected in the cell; 65534 marks a cell with no flag data.
+"""
+
+FILL_VALUE = 65534
+
+
+class SurfaceFlag(enum.IntFlag):
+    STATIC_WATER = 1 << 0
+    RADAR_WATER = 1 << 1
+    COASTAL_PROXIMITY = 1 << 2
+    URBAN_AREA = 1 << 3
+    PRECIPITATION = 1 << 4
+    SNOW_OR_ICE = 1 << 5
+    PERMANENT_SNOW_OR_ICE = 1 << 6
+    RADIOMETER_FROZEN_GROUND = 1 << 7
+    MODEL_FROZEN_GROUND = 1 << 8
+    MOUNTAINOUS_TERRAIN = 1 << 9
+    DENSE_VEGETATION = 1 << 10
+    NADIR_REGION = 1 << 11
+
+
+# Named groups that can be excluded when selecting pixels
+EXCLUDE_GROUPS = {
+    'water': SurfaceFlag.STATIC_WATER | SurfaceFlag.RADAR_WATER,
+    'frozen': SurfaceFlag.RADIOMETER_FROZEN_GROUND | SurfaceFlag.MODEL_FROZEN_GROUND |
+              SurfaceFlag.SNOW_OR_ICE | SurfaceFlag.PERMANENT_SNOW_OR_ICE,
+    'dense_vegetation': SurfaceFlag.DENSE_VEGETATION,
+    'mountainous': SurfaceFlag.MOUNTAINOUS_TERRAIN
+}
+# Conditions under which retrievals are known to be unreliable
+RECOMMENDED_EXCLUDE = EXCLUDE_GROUPS['water'] | EXCLUDE_GROUPS['frozen'] | EXCLUDE_GROUPS['dense_vegetation']
+
+
+def decode(value: Optional[int]) -> Optional[Dict[str, bool]]:
+    """Every named bit as a lowercase key, or None for fill values"""
+    if value is None or value == FILL_VALUE:
+        return None
+    # Bits above the documented ones are unused
+    flags = SurfaceFlag(value & 0xFFF)
+    return {flag.name.lower(): bool(flags & flag) for flag in SurfaceFlag}
+
+
+def exclusion_mask(groups: Iterable[str]) -> SurfaceFlag:
+    """Combine named groups into one mask, raising ValueError for unknown names"""
+    mask = SurfaceFlag(0)
+    for group in groups:
+        if group not in EXCLUDE_GROUPS:
+            raise ValueError(f"Unknown surface flag group: {group} (expected one of {', '.join(EXCLUDE_GROUPS)})")
+        mask |= EXCLUDE_GROUPS[group]
+    return mask
+
+
+def excluded(value: Optional[int], mask: SurfaceFlag) -> bool:
+    """Whether a cell's flags hit any bit in mask; fill values are never excluded"""
+    if value is None or value == FILL_VALUE:
+        return False
+    return bool(value & mask)
@@ -0,0 +1,88 @@
+import os
+import json
+from typing import Dict, Optional, Union
+
+"""
+Response format negotiation shared by every JSON-producing API route
+"""
+
+JSON = 'application/json'
+MSGPACK = 'application/msgpack'
+FORMATS = {'json': JSON, 'msgpack': MSGPACK}
+MSGPACK_ALIASES = (MSGPACK, 'application/x-msgpack', 'application/vnd.msgpack')
+# SMAP granules store float32, which holds about 7 significant digits; past that a widened
+# value is noise (0.23 arrives as 0.23000000417232513). 0 turns rounding off
+SIGNIFICANT_DIGITS = int(os.getenv('OPENFLOW_SIGNIFICANT_DIGITS', '7'))
+MAX_SIGNIFICANT_DIGITS = 17
+
+
+def negotiate(format_param: Optional[str], accept: Optional[str]) -> str:
+    """Pick a media type from ?format= (which wins) or the Accept header, defaulting to JSON"""
+    if format_param:
+        if format_param not in FORMATS:
+            raise ValueError(f"format must be one of: {', '.join(FORMATS)}")
+        return FORMATS[format_param]
+    ranges = parse_accept(accept or '')
+    listed = [ranges[alias] for alias in MSGPACK_ALIASES if alias in ranges]
+    # Naming msgpack, under any alias, overrides wildcards: msgpack;q=0, */* refuses it
+    msgpack = max(listed) if listed else max(_quality(ranges, alias) for alias in MSGPACK_ALIASES)
+    json_quality = _quality(ranges, JSON)
+    # JSON wins a tie unless msgpack was asked for by name, as a */* client expects JSON
+    named = any(quality > 0 for quality in listed)
+    if msgpack > json_quality or (msgpack and msgpack == json_quality and named):
+        return MSGPACK
+    return JSON
+
+
+def parse_accept(accept: str) -> Dict[str, float]:
+    """Media ranges of an Accept header with their q-values; ranges with a malformed q are dropped"""
+    ranges = {}
+    for item in accept.split(','):
+        media_range, *params = [part.strip() for part in item.split(';')]
+        if not media_range:
+            continue
+        quality = 1.0
+        for param in params:
+            name, _, value = param.partition('=')
+            if name.strip().lower() == 'q':
+                try:
+                    quality = float(value)
+                except ValueError:
+                    quality = None
+        if quality is not None and 0 <= quality <= 1:
+            ranges[media_range.lower()] = max(quality, ranges.get(media_range.lower(), 0))
+    return ranges
+
+
+def _quality(ranges: Dict[str, float], media_type: str) -> float:
+    """q-value the most specific matching range gives a media type, 0 when none matches"""
+    for media_range in (media_type, media_type.split('/')[0] + '/*', '*/*'):
+        if media_range in ranges:
+            return ranges[media_range]
+    return 0.0
+
+
+def round_significant(value, digits: Optional[int]):
+    """Round a float to a number of significant digits; anything else, or digits of 0/None, passes through"""
+    if not digits or not isinstance(value, float):
+        return value
+    return float(f"{value:.{digits}g}")
+
+
+def round_floats(payload, digits: Optional[int]):
+    """Apply round_significant to every float in a payload of dicts and lists"""
+    if isinstance(payload, dict):
+        return {key: round_floats(value, digits) for key, value in payload.items()}
+    if isinstance(payload, (list, tuple)):
+        return [round_floats(value, digits) for value in payload]
+    return round_significant(payload, digits)
+
+
+def encode(payload, media_type: str, digits: Optional[int] = None) -> Union[str, bytes]:
+    """Serialize a payload for the negotiated media type, floats rounded to digits significant digits"""
+    if digits:
+        payload = round_floats(payload, digits)
+    if media_type == MSGPACK:
+        import msgpack
+        return msgpack.packb(payload, use_bin_type=True)
+    return json.dumps(payload)
@@ -0,0 +1,86 @@
+import os
+import shutil
+import logging
+import string
+from datetime import datetime
+from pathlib import Path
+from typing import List
+from urllib.parse import unquote, urlparse
+
+import http_client
+
+"""
+Granule download sources. OPENFLOW_GRANULE_SOURCES is a comma-separated list tried in
+order until one serves the file. Each entry is either `earthdata` (earthaccess's own
+download from NSIDC) or a URL template for a mirror, e.g.
+
+    s3://smap-mirror/SPL3SMP_E.{version}/{yyyy}.{mm}.{dd}/{granule},earthdata
+
+Templates may use {yyyy}, {mm}, {dd}, {version} and {granule} (the granule file name)
+and the https, http, file or s3 schemes; s3 needs boto3 installed.
+"""
+
+logger = logging.getLogger(__name__)
+
+EARTHDATA = 'earthdata'
+PLACEHOLDERS = {'yyyy', 'mm', 'dd', 'version', 'granule'}
+SCHEMES = ('https', 'http', 'file', 's3')
+SOURCES = os.getenv('OPENFLOW_GRANULE_SOURCES', EARTHDATA)
+
+
+def parse_sources(spec: str) -> List[str]:
+    """Split and validate a source list, raising ValueError for anything malformed"""
+    sources = [entry.strip() for entry in spec.split(',') if entry.strip()]
+    if not sources:
+        raise ValueError("at least one granule source is required")
+    for source in sources:
+        if source == EARTHDATA:
+            continue
+        scheme = urlparse(source).scheme
+        if scheme not in SCHEMES:
+            raise ValueError(f"granule source {source!r} must be '{EARTHDATA}' or use one of: {', '.join(SCHEMES)}")
+        try:
+            fields = {field for _, field, _, _ in string.Formatter().parse(source) if field is not None}
+        except ValueError as e:
+            raise ValueError(f"granule source {source!r} is not a valid template: {e}") from None
+        unknown = fields - PLACEHOLDERS
+        if unknown:
+            raise ValueError(f"granule source {source!r} uses unknown placeholders: {', '.join(sorted(unknown))}")
+        if 'granule' not in fields:
+            raise ValueError(f"granule source {source!r} must include {{granule}}")
+        if scheme == 's3':
+            try:
+                import boto3  # noqa: F401
+            except ImportError:
+                raise ValueError("s3:// granule sources need boto3 installed") from None
+    return sources
+
+
+def expand(template: str, day: datetime, version: str, granule: str) -> str:
+    """Fill a URL template for one granule"""
+    return template.format(yyyy=f'{day.year:04d}', mm=f'{day.month:02d}', dd=f'{day.day:02d}',
+                           version=version, granule=granule)
+
+
+def fetch(url: str, dest_dir: Path) -> Path:
+    """Download one URL into dest_dir, keeping its file name; raises on any failure"""
+    parsed = urlparse(url)
+    target = Path(dest_dir) / Path(unquote(parsed.path)).name
+    partial = target.with_name(target.name + '.part')
+    try:
+        if parsed.scheme == 'file':
+            shutil.copyfile(unquote(parsed.path), partial)
+        elif parsed.scheme == 's3':
+            import boto3
+            boto3.client('s3').download_file(parsed.netloc, parsed.path.lstrip('/'), str(partial))
+        else:
+            with http_client.session().get(url, stream=True) as response:
+                response.raise_for_status()
+                with open(partial, 'wb') as f:
+                    for chunk in response.iter_content(chunk_size=1 << 20):
+                        f.write(chunk)
+        # Only a complete download gets the real name
+        partial.replace(target)
+    finally:
+        partial.unlink(missing_ok=True)
+    return target
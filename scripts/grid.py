@@ -0,0 +1,113 @@
+import math
+from dataclasses import dataclass
+from typing import Dict, List, Tuple
+
+"""
+EASE-Grid 2.0 global grids (EPSG:6933): a Lambert cylindrical equal-area projection of
+the WGS84 ellipsoid with a 30 degree standard parallel (Brodzik et al., 2012). Forward
+and inverse formulas follow Snyder, Map Projections (1987), eqs. 10-15 and 3-18.
+"""
+
+SEMI_MAJOR_AXIS = 6378137.0
+FLATTENING = 1 / 298.257223563
+E2 = FLATTENING * (2 - FLATTENING)
+E = math.sqrt(E2)
+STANDARD_PARALLEL = 30.0
+
+
+def _q(phi: float) -> float:
+    sin_phi = math.sin(phi)
+    return (1 - E2) * (sin_phi / (1 - E2 * sin_phi ** 2) -
+                       1 / (2 * E) * math.log((1 - E * sin_phi) / (1 + E * sin_phi)))
+
+
+_PHI_1 = math.radians(STANDARD_PARALLEL)
+K0 = math.cos(_PHI_1) / math.sqrt(1 - E2 * math.sin(_PHI_1) ** 2)
+Q_POLE = _q(math.pi / 2)
+
+
+def forward(latitude: float, longitude: float) -> Tuple[float, float]:
+    """Project degrees to EASE-Grid 2.0 map metres (x, y)"""
+    x = SEMI_MAJOR_AXIS * K0 * math.radians(longitude)
+    y = SEMI_MAJOR_AXIS * _q(math.radians(latitude)) / (2 * K0)
+    return x, y
+
+
+def inverse(x: float, y: float) -> Tuple[float, float]:
+    """Map metres back to (latitude, longitude) degrees"""
+    beta = math.asin(max(-1.0, min(1.0, 2 * y * K0 / (SEMI_MAJOR_AXIS * Q_POLE))))
+    phi = (beta +
+           (E2 / 3 + 31 * E2 ** 2 / 180 + 517 * E2 ** 3 / 5040) * math.sin(2 * beta) +
+           (23 * E2 ** 2 / 360 + 251 * E2 ** 3 / 3780) * math.sin(4 * beta) +
+           (761 * E2 ** 3 / 45360) * math.sin(6 * beta))
+    return math.degrees(phi), math.degrees(x / (SEMI_MAJOR_AXIS * K0))
+
+
+@dataclass(frozen=True)
+class Grid:
+    """A global EASE-Grid 2.0 grid; row 0 is the northernmost row, column 0 starts at 180 W"""
+    name: str
+    resolution_km: int
+    cell_size: float  # Metres, in both x and y
+    cols: int
+    rows: int
+
+    @property
+    def origin(self) -> Tuple[float, float]:
+        """Map coordinates of the upper-left corner of cell (0, 0)"""
+        return -self.cols * self.cell_size / 2, self.rows * self.cell_size / 2
+
+    def cell(self, latitude: float, longitude: float) -> Tuple[int, int]:
+        """(row, col) of the cell containing a point; ValueError outside the grid's latitude band"""
+        x, y = forward(latitude, ((longitude + 180) % 360) - 180)
+        x0, y0 = self.origin
+        row = math.floor((y0 - y) / self.cell_size)
+        col = min(math.floor((x - x0) / self.cell_size), self.cols - 1)
+        if not 0 <= row < self.rows:
+            north, _ = inverse(0, y0)
+            raise ValueError(f"latitude must be between -{north:.4f} and {north:.4f} for {self.name}")
+        return row, col
+
+    def center(self, row: int, col: int) -> Tuple[float, float]:
+        """(latitude, longitude) of a cell's center"""
+        self._check(row, col)
+        x0, y0 = self.origin
+        return inverse(x0 + (col + 0.5) * self.cell_size, y0 - (row + 0.5) * self.cell_size)
+
+    def bounds(self, row: int, col: int) -> Dict[str, float]:
+        """A cell's edges in degrees; cells are lat/lon rectangles in a cylindrical projection"""
+        self._check(row, col)
+        x0, y0 = self.origin
+        north, west = inverse(x0 + col * self.cell_size, y0 - row * self.cell_size)
+        south, east = inverse(x0 + (col + 1) * self.cell_size, y0 - (row + 1) * self.cell_size)
+        return {'north': north, 'south': south, 'west': west, 'east': east}
+
+    def corners(self, row: int, col: int) -> List[Tuple[float, float]]:
+        """(latitude, longitude) corners, counter-clockwise from the north-west"""
+        b = self.bounds(row, col)
+        return [(b['north'], b['west']), (b['south'], b['west']),
+                (b['south'], b['east']), (b['north'], b['east'])]
+
+    def window(self, latitude: float, longitude: float, radius_km: float) -> Tuple[slice, slice]:
+        """Row and column slices covering every cell within radius_km of a point"""
+        row, col = self.cell(latitude, longitude)
+        phi = math.radians(latitude)
+        # Ground distance per map metre along a parallel; 1/h along a meridian
+        h = math.cos(phi) / (K0 * math.sqrt(1 - E2 * math.sin(phi) ** 2))
+        radius = radius_km * 1000
+        d_col = math.ceil(radius / (self.cell_size * h)) + 1
+        d_row = math.ceil(radius * h / self.cell_size) + 1
+        return (slice(max(0, row - d_row), min(self.rows, row + d_row + 1)),
+                slice(max(0, col - d_col), min(self.cols, col + d_col + 1)))
+
+    def _check(self, row: int, col: int):
+        if not (0 <= row < self.rows and 0 <= col < self.cols):
+            raise ValueError(f"row must be 0-{self.rows - 1} and col 0-{self.cols - 1} for {self.name}")
+
+
+GRIDS = {
+    'M36': Grid('EASE2_M36km', 36, 36032.220840584, 964, 406),
+    'M09': Grid('EASE2_M09km', 9, 9008.055210146, 3856, 1624)
+}
+# Grid of each SMAP product whose data lands on a global EASE-Grid 2.0 grid
+PRODUCT_GRIDS = {'SPL3SMP': 'M36', 'SPL3SMP_E': 'M09'}
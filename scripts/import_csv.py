@@ -0,0 +1,158 @@
+import os
+import csv
+import json
+import sqlite3
+import logging
+import argparse
+from datetime import date, datetime, timezone
+from typing import Dict, Iterable, List, Optional, TextIO
+
+import anomaly
+import integrity
+
+"""
+Bulk import of historical soil moisture records (e.g. from a previous system) into
+processed_data. Expects a CSV with a header row:
+
+    date,lat,lon,moisture
+
+Rows are stored under the location "lat,lon". The file is read row by row and
+committed in batches; invalid or already-stored rows are skipped and reported unless
+--strict is given, in which case the first one aborts the import and nothing is written:
+
+    python3 import_csv.py history.csv [--strict]
+
+Imports bypass the anomaly checks (old data would skew the trailing baseline) but are
+recorded in ingestion_runs with status 'imported', and their dates get integrity checksums.
+"""
+
+logger = logging.getLogger(__name__)
+
+COLUMNS = ('date', 'lat', 'lon', 'moisture')
+BATCH_ROWS = 1000
+MAX_IMPORT_BYTES = int(os.getenv('OPENFLOW_IMPORT_MAX_BYTES', str(200 * 1024 * 1024)))
+# Rejection reasons kept for the report; the counts cover every row
+MAX_REPORTED_ERRORS = 20
+
+
+class ImportAborted(ValueError):
+    """A strict import hit an invalid row"""
+
+
+def parse_row(row: Dict) -> tuple:
+    """(date, location, moisture) from a CSV row, raising ValueError naming the column at fault"""
+    try:
+        day = date.fromisoformat((row.get('date') or '').strip()).isoformat()
+    except ValueError:
+        raise ValueError("date must be a date in YYYY-MM-DD format")
+    values = {}
+    for column, low, high in (('lat', -90, 90), ('lon', -180, 180), ('moisture', 0, 1)):
+        try:
+            values[column] = float(row.get(column))
+        except (TypeError, ValueError):
+            raise ValueError(f"{column} must be a number")
+        # Also rejects NaN, which compares false with everything
+        if not low <= values[column] <= high:
+            raise ValueError(f"{column} must be between {low} and {high}")
+    return day, f"{values['lat']:g},{values['lon']:g}", values['moisture']
+
+
+def import_rows(conn: sqlite3.Connection, rows: Iterable[Dict], strict: bool = False,
+                source: Optional[str] = None) -> Dict:
+    """Validate and insert rows in batches, returning a report of what was inserted and rejected"""
+    conn.execute('''CREATE TABLE IF NOT EXISTS processed_data
+                    (date TEXT, location TEXT, smap_value REAL, vegdri_value REAL)''')
+    anomaly.create_tables(conn)
+    report: Dict = {'inserted': 0, 'rejected': 0, 'errors': []}
+    batch: List[tuple] = []
+
+    def reject(line, reason):
+        if strict:
+            raise ImportAborted(f"line {line}: {reason}")
+        report['rejected'] += 1
+        if len(report['errors']) < MAX_REPORTED_ERRORS:
+            report['errors'].append({'line': line, 'error': reason})
+
+    def flush():
+        integrity.record(conn, {day for day, _, _ in batch})
+        if not strict:
+            conn.commit()
+        report['inserted'] += len(batch)
+        batch.clear()
+
+    try:
+        # Line 1 is the header
+        for line, row in enumerate(rows, start=2):
+            try:
+                day, location, moisture = parse_row(row)
+            except ValueError as e:
+                reject(line, str(e))
+                continue
+            # Also catches a repeat within the file, as earlier rows are inserted already
+            if conn.execute('SELECT 1 FROM processed_data WHERE date = ? AND location = ?',
+                            (day, location)).fetchone():
+                reject(line, f"{location} already has a value for {day}")
+                continue
+            conn.execute('''INSERT INTO processed_data (date, location, smap_value, vegdri_value)
+                            VALUES (?, ?, ?, NULL)''', (day, location, moisture))
+            batch.append((day, location, moisture))
+            if len(batch) >= BATCH_ROWS:
+                flush()
+        if batch:
+            flush()
+        conn.execute('''
+            INSERT INTO ingestion_runs (finished_at, row_count, mean, valid_fraction, locations, status, reason)
+            VALUES (?, ?, NULL, NULL, NULL, 'imported', ?)
+        ''', (datetime.now(timezone.utc).isoformat(), report['inserted'], f"from {source}" if source else None))
+        conn.commit()
+    except BaseException:
+        # Strict imports are one transaction; otherwise only the unfinished batch is lost
+        conn.rollback()
+        raise
+    return report
+
+
+def open_csv(path: str) -> TextIO:
+    """Open an import file after checking its size and header"""
+    size = os.path.getsize(path)
+    if size > MAX_IMPORT_BYTES:
+        raise ValueError(f"{path} is {size} bytes, over the {MAX_IMPORT_BYTES} byte limit (OPENFLOW_IMPORT_MAX_BYTES)")
+    f = open(path, newline='')
+    header = next(csv.reader(f), [])
+    f.seek(0)
+    missing = [column for column in COLUMNS if column not in [name.strip() for name in header]]
+    if missing:
+        f.close()
+        raise ValueError(f"header is missing {', '.join(missing)}; expected {','.join(COLUMNS)}")
+    return f
+
+
+def main():
+    logging.basicConfig(level=logging.INFO)
+    parser = argparse.ArgumentParser(description="Import historical soil moisture records from CSV")
+    parser.add_argument('csv_file', help="CSV with date,lat,lon,moisture")
+    parser.add_argument('--strict', action='store_true', help="Abort without writing anything on the first bad row")
+    parser.add_argument('--db', default=os.getenv('OPENFLOW_DB_PATH', '/var/lib/openflow/data.db'),
+                        help="API database path (default OPENFLOW_DB_PATH)")
+    args = parser.parse_args()
+
+    try:
+        f = open_csv(args.csv_file)
+    except (OSError, ValueError) as e:
+        parser.error(str(e))
+    conn = sqlite3.connect(args.db)
+    try:
+        with f:
+            reader = csv.DictReader(f, skipinitialspace=True)
+            report = import_rows(conn, reader, args.strict, os.path.basename(args.csv_file))
+    except ImportAborted as e:
+        logger.error(f"Import aborted, nothing written: {e}")
+        raise SystemExit(1)
+    finally:
+        conn.close()
+    print(json.dumps(report, indent=2))
+    logger.info(f"Imported {report['inserted']} rows into {args.db}, rejected {report['rejected']}")
+
+
+if __name__ == "__main__":
+    main()
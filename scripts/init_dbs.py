@@ -48,14 +48,15 @@ def setup_database(db_path: Path):
     with sqlite3.connect(db_path) as conn:
         if exists:
             if check_database_structure(conn):
+                # Fall through so tables added since the database was created get made
                 logger.info("Database structure verified")
-                return
             else:
                 logger.warning("Database exists but structure is incorrect. Dropping and recreating tables...")
                 # Drop existing tables in correct order (due to foreign keys)
                 conn.execute("DROP TABLE IF EXISTS smap_features")
                 conn.execute("DROP TABLE IF EXISTS vegetation_features")
                 conn.execute("DROP TABLE IF EXISTS snow_features")
+                conn.execute("DROP TABLE IF EXISTS precipitation_features")
                 conn.execute("DROP TABLE IF EXISTS static_features")
                 conn.execute("DROP TABLE IF EXISTS stations")
         
@@ -85,13 +86,23 @@ def setup_database(db_path: Path):
                 station_id TEXT,
                 soil_moisture REAL,      -- Normalized soil moisture (0-1)
                 quality_flag INTEGER,    -- Original SMAP quality flag (0-1)
+                surface_flag INTEGER,    -- SMAP surface_flag bits of the station's cell (see flags.py)
+                observation_time TEXT,   -- RFC 3339 UTC overpass time, null when the granule has none
                 trend3 REAL,             -- 3-day trend
                 source INTEGER,          -- Binary: 0=L3, 1=L4
+                synthetic INTEGER DEFAULT 0, -- 1 for fake rows from synthetic mode (smapprocessor.py)
                 PRIMARY KEY (timestamp, station_id),
                 FOREIGN KEY (station_id) REFERENCES stations(id)
             )
         ''')
         
+        # Columns added after release are migrated in place rather than forcing a recreate
+        smap_columns = [row[1] for row in conn.execute("PRAGMA table_info(smap_features)")]
+        for column, declaration in (('surface_flag', 'INTEGER'), ('observation_time', 'TEXT'),
+                                    ('synthetic', 'INTEGER DEFAULT 0')):
+            if column not in smap_columns:
+                conn.execute(f"ALTER TABLE smap_features ADD COLUMN {column} {declaration}")
+        
         # Create vegetation features table
         conn.execute('''
             CREATE TABLE IF NOT EXISTS vegetation_features (
@@ -116,6 +127,17 @@ def setup_database(db_path: Path):
             )
         ''')
         
+        # Create precipitation features table
+        conn.execute('''
+            CREATE TABLE IF NOT EXISTS precipitation_features (
+                timestamp INTEGER,
+                station_id TEXT,
+                precipitation REAL,      -- Daily total in mm (Open-Meteo archive)
+                PRIMARY KEY (timestamp, station_id),
+                FOREIGN KEY (station_id) REFERENCES stations(id)
+            )
+        ''')
+        
         logger.info("Database tables created successfully")
 
 
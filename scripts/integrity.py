@@ -0,0 +1,162 @@
+import os
+import random
+import sqlite3
+import hashlib
+import logging
+import argparse
+import time
+from datetime import datetime, timezone
+from typing import Dict, Iterable, List, Optional
+
+import notify
+
+"""
+Stored data integrity checks. Whenever rows for a date are published to processed_data,
+the date's row count and a checksum of its values are recorded in date_checksums. A
+verification pass recomputes both for a sample of recorded dates; a date that no longer
+matches (silent disk corruption, a stray manual edit) is marked suspect and reported:
+
+    python3 integrity.py verify [--sample 0.1] [--days 365]
+    python3 integrity.py baseline     # record dates published before checksums existed
+    python3 integrity.py suspect
+"""
+
+logger = logging.getLogger(__name__)
+
+# Fraction of recorded dates checked per pass
+SAMPLE_RATE = float(os.getenv('OPENFLOW_VERIFY_SAMPLE', '0.1'))
+# Seconds to wait between dates, so a pass doesn't compete with API queries
+PAUSE = float(os.getenv('OPENFLOW_VERIFY_PAUSE', '0.5'))
+
+
+def create_tables(conn: sqlite3.Connection):
+    conn.execute('''
+        CREATE TABLE IF NOT EXISTS date_checksums (
+            date TEXT PRIMARY KEY,
+            row_count INTEGER,
+            checksum TEXT,          -- SHA-256 of the date's rows in a canonical order
+            recorded_at TEXT,
+            status TEXT,            -- ok, suspect
+            verified_at TEXT
+        )
+    ''')
+
+
+def checksum(conn: sqlite3.Connection, day: str) -> Dict:
+    """Row count and checksum of one date's rows in processed_data"""
+    digest = hashlib.sha256()
+    count = 0
+    for location, smap_value, vegdri_value in conn.execute(
+            '''SELECT location, smap_value, vegdri_value FROM processed_data
+               WHERE date = ? ORDER BY location, smap_value, vegdri_value''', (day,)):
+        digest.update(f"{location}\t{smap_value!r}\t{vegdri_value!r}\n".encode('utf-8'))
+        count += 1
+    return {'row_count': count, 'checksum': digest.hexdigest()}
+
+
+def record(conn: sqlite3.Connection, days: Iterable[str]):
+    """Record the current state of each date as its reference; joins the caller's transaction"""
+    create_tables(conn)
+    now = datetime.now(timezone.utc).isoformat()
+    for day in sorted(set(days)):
+        figures = checksum(conn, day)
+        conn.execute('''INSERT OR REPLACE INTO date_checksums (date, row_count, checksum, recorded_at, status)
+                        VALUES (?, ?, ?, ?, 'ok')''', (day, figures['row_count'], figures['checksum'], now))
+
+
+def verify(conn: sqlite3.Connection, sample_rate: float = SAMPLE_RATE, days: Optional[int] = None,
+           pause: float = PAUSE) -> List[Dict]:
+    """
+    Recompute a sample of recorded dates, marking and returning those that no longer match
+
+    Suspect dates are marked, and an integrity_mismatch notification queued in the outbox,
+    in one transaction at the end of the pass
+    """
+    create_tables(conn)
+    query = 'SELECT date, row_count, checksum FROM date_checksums'
+    params = ()
+    if days is not None:
+        query += " WHERE date >= date((SELECT MAX(date) FROM date_checksums), ?)"
+        params = (f'-{days - 1} days',)
+    recorded = conn.execute(query, params).fetchall()
+    sample = random.sample(recorded, min(len(recorded), max(1, round(len(recorded) * sample_rate)))) \
+        if recorded else []
+
+    discrepancies = []
+    for i, (day, row_count, expected) in enumerate(sorted(sample)):
+        if i and pause:
+            time.sleep(pause)
+        figures = checksum(conn, day)
+        if figures['row_count'] == row_count and figures['checksum'] == expected:
+            with conn:
+                conn.execute("UPDATE date_checksums SET status = 'ok', verified_at = ? WHERE date = ?",
+                             (datetime.now(timezone.utc).isoformat(), day))
+        else:
+            discrepancies.append({'date': day, 'expected_rows': row_count, 'rows': figures['row_count']})
+    if discrepancies:
+        with conn:
+            conn.executemany("UPDATE date_checksums SET status = 'suspect', verified_at = ? WHERE date = ?",
+                             [(datetime.now(timezone.utc).isoformat(), d['date']) for d in discrepancies])
+            if notify.configured():
+                notify.enqueue(conn, 'integrity_mismatch', count=len(discrepancies), db_path=db_path(conn),
+                               dates='\n'.join(f"{d['date']}: {d['rows']} rows, {d['expected_rows']} at ingestion"
+                                               for d in discrepancies))
+    logger.info(f"Verified {len(sample)} of {len(recorded)} recorded dates, {len(discrepancies)} suspect")
+    return discrepancies
+
+
+def db_path(conn: sqlite3.Connection) -> str:
+    """File behind a connection's main database, for messages"""
+    return conn.execute('PRAGMA database_list').fetchone()[2] or ':memory:'
+
+
+def suspect_dates(conn: sqlite3.Connection, start: str, end: str) -> List[str]:
+    """Dates in a range whose stored rows failed verification; none where nothing is recorded"""
+    try:
+        return [row[0] for row in conn.execute(
+            "SELECT date FROM date_checksums WHERE status = 'suspect' AND date BETWEEN ? AND ? ORDER BY date",
+            (start, end))]
+    except sqlite3.OperationalError:
+        # No checksums recorded in this database yet
+        return []
+
+
+def main():
+    logging.basicConfig(level=logging.INFO)
+    parser = argparse.ArgumentParser(description="Verify stored data against checksums recorded at ingestion")
+    parser.add_argument('action', choices=('verify', 'baseline', 'suspect'))
+    parser.add_argument('--sample', type=float, default=SAMPLE_RATE,
+                        help="Fraction of recorded dates to check (default OPENFLOW_VERIFY_SAMPLE)")
+    parser.add_argument('--days', type=int, help="Only check dates within this many days of the latest")
+    parser.add_argument('--db', default=os.getenv('OPENFLOW_DB_PATH', '/var/lib/openflow/data.db'),
+                        help="API database path (default OPENFLOW_DB_PATH)")
+    args = parser.parse_args()
+    if not 0 < args.sample <= 1:
+        parser.error("--sample must be above 0 and at most 1")
+
+    conn = sqlite3.connect(args.db)
+    try:
+        create_tables(conn)
+        if args.action == 'baseline':
+            with conn:
+                missing = [row[0] for row in conn.execute(
+                    'SELECT DISTINCT date FROM processed_data WHERE date NOT IN (SELECT date FROM date_checksums)')]
+                record(conn, missing)
+            logger.info(f"Recorded checksums for {len(missing)} dates")
+        elif args.action == 'suspect':
+            for row in conn.execute("SELECT date, row_count, verified_at FROM date_checksums "
+                                    "WHERE status = 'suspect' ORDER BY date"):
+                print(*row, sep='\t')
+        else:
+            discrepancies = verify(conn, args.sample, args.days)
+            if discrepancies:
+                # The alert is already committed in the outbox; try delivering it now
+                if notify.configured():
+                    notify.dispatch(conn)
+                raise SystemExit(1)
+    finally:
+        conn.close()
+
+
+if __name__ == "__main__":
+    main()
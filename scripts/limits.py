@@ -0,0 +1,80 @@
+import os
+import threading
+from typing import Dict, Iterable, Optional
+
+"""
+Concurrency limits for API routes: a ceiling on requests in flight across the whole
+server plus optional per-route ceilings, so a burst of heavy exports can't tie up every
+worker thread. Requests over a limit are turned away immediately rather than queued.
+"""
+
+GLOBAL = '*'
+MAX_IN_FLIGHT = int(os.getenv('OPENFLOW_MAX_IN_FLIGHT', '8'))
+# Comma-separated route=limit pairs, e.g. "/export=2,/compare=4"
+ROUTE_LIMITS = os.getenv('OPENFLOW_ROUTE_LIMITS', '/export=2')
+RETRY_AFTER = int(os.getenv('OPENFLOW_RETRY_AFTER', '1'))
+
+
+def parse_route_limits(spec: str) -> Dict[str, int]:
+    """Parse "route=limit,..." into a dict, raising ValueError on malformed entries"""
+    limits = {}
+    for entry in filter(None, (part.strip() for part in spec.split(','))):
+        route, _, value = entry.partition('=')
+        if not route.startswith('/') or not value.isdigit() or int(value) < 1:
+            raise ValueError(f"Route limit must look like /route=N with N >= 1, got {entry!r}")
+        limits[route] = int(value)
+    return limits
+
+
+class ConcurrencyLimiter:
+    """Counts requests in flight, globally and per limited route"""
+
+    def __init__(self, global_limit: int, route_limits: Optional[Dict[str, int]] = None):
+        self.limits = {GLOBAL: global_limit, **(route_limits or {})}
+        self.in_flight = {key: 0 for key in self.limits}
+        self._lock = threading.Lock()
+
+    def acquire(self, route: str) -> bool:
+        """Take a slot for route, or return False without waiting if one isn't free"""
+        keys = [GLOBAL] + ([route] if route in self.limits else [])
+        with self._lock:
+            if any(self.in_flight[key] >= self.limits[key] for key in keys):
+                return False
+            for key in keys:
+                self.in_flight[key] += 1
+        return True
+
+    def release(self, route: str):
+        with self._lock:
+            for key in [GLOBAL] + ([route] if route in self.limits else []):
+                self.in_flight[key] -= 1
+
+    def snapshot(self) -> Dict[str, Dict[str, int]]:
+        """In-flight count and limit for the server and each limited route"""
+        with self._lock:
+            return {key: {'in_flight': self.in_flight[key], 'limit': self.limits[key]} for key in self.limits}
+
+    def hold(self, iterable: Iterable, route: str) -> 'HeldIterable':
+        """Keep route's slot until a streamed response body is closed"""
+        return HeldIterable(iterable, lambda: self.release(route))
+
+
+class HeldIterable:
+    """Response body that runs a callback once when the server closes it"""
+
+    def __init__(self, iterable: Iterable, on_close):
+        self._iterable = iterable
+        self._on_close = on_close
+
+    def __iter__(self):
+        return iter(self._iterable)
+
+    def close(self):
+        # WSGI servers call close() whether the body was fully sent, abandoned, or never started
+        try:
+            if hasattr(self._iterable, 'close'):
+                self._iterable.close()
+        finally:
+            on_close, self._on_close = self._on_close, None
+            if on_close:
+                on_close()
@@ -0,0 +1,91 @@
+import os
+import csv
+import sqlite3
+import logging
+import argparse
+from typing import Dict, Iterable, List
+
+"""
+Import per-location soil properties (e.g. derived from SoilGrids) for the API's
+plant-available water option. Expects a CSV with a header row:
+
+    location,sand,clay,field_capacity,wilting_point
+
+All values except location are volumetric or mass fractions between 0 and 1.
+"""
+
+logger = logging.getLogger(__name__)
+
+FRACTION_COLUMNS = ('sand', 'clay', 'field_capacity', 'wilting_point')
+
+
+def create_table(conn: sqlite3.Connection):
+    """Create the soil_properties table if it doesn't exist"""
+    conn.execute('''
+        CREATE TABLE IF NOT EXISTS soil_properties (
+            location TEXT PRIMARY KEY,  -- Matches processed_data.location
+            sand REAL,
+            clay REAL,
+            field_capacity REAL,        -- m3/m3
+            wilting_point REAL          -- m3/m3
+        )
+    ''')
+
+
+def parse_rows(rows: Iterable[Dict]) -> List[tuple]:
+    """Validate CSV rows, raising ValueError naming the line and column at fault"""
+    parsed = []
+    # Line 1 is the header
+    for line, row in enumerate(rows, start=2):
+        location = (row.get('location') or '').strip()
+        if not location:
+            raise ValueError(f"line {line}: location is required")
+        values = {}
+        for column in FRACTION_COLUMNS:
+            try:
+                values[column] = float(row[column])
+            except (KeyError, TypeError, ValueError):
+                raise ValueError(f"line {line}: {column} must be a number")
+            if not 0 <= values[column] <= 1:
+                raise ValueError(f"line {line}: {column} must be between 0 and 1")
+        if values['sand'] + values['clay'] > 1:
+            raise ValueError(f"line {line}: sand and clay fractions add up to more than 1")
+        if values['field_capacity'] <= values['wilting_point']:
+            raise ValueError(f"line {line}: field_capacity must be greater than wilting_point")
+        parsed.append((location, *(values[column] for column in FRACTION_COLUMNS)))
+    return parsed
+
+
+def load_soil_properties(conn: sqlite3.Connection, rows: Iterable[Dict]) -> int:
+    """Validate and upsert rows; nothing is written if any row is invalid"""
+    parsed = parse_rows(rows)
+    create_table(conn)
+    with conn:
+        conn.executemany('''
+            INSERT OR REPLACE INTO soil_properties
+            (location, sand, clay, field_capacity, wilting_point)
+            VALUES (?, ?, ?, ?, ?)
+        ''', parsed)
+    return len(parsed)
+
+
+def main():
+    logging.basicConfig(level=logging.INFO)
+    parser = argparse.ArgumentParser(description="Import soil properties for plant-available water")
+    parser.add_argument('csv_file', help="CSV with location,sand,clay,field_capacity,wilting_point")
+    parser.add_argument('--db', default=os.getenv('OPENFLOW_DB_PATH', '/var/lib/openflow/data.db'),
+                        help="API database path (default OPENFLOW_DB_PATH)")
+    args = parser.parse_args()
+
+    with open(args.csv_file, newline='') as f:
+        rows = list(csv.DictReader(f))
+    conn = sqlite3.connect(args.db)
+    try:
+        count = load_soil_properties(conn, rows)
+    finally:
+        conn.close()
+    logger.info(f"Imported soil properties for {count} locations into {args.db}")
+
+
+if __name__ == "__main__":
+    main()
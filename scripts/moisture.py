@@ -0,0 +1,17 @@
+from typing import Optional
+
+"""
+Which stored soil moisture values are observations. SMAP retrievals are volumetric
+fractions; anything outside the valid range (e.g. -9999) is a fill value, not an
+observation, and queries and checks share these definitions so they agree.
+"""
+
+VALID_RANGE = (0, 1)
+# SQL condition on processed_data.smap_value; prefix with a table alias where needed
+VALID_MOISTURE = f'smap_value BETWEEN {VALID_RANGE[0]} AND {VALID_RANGE[1]}'
+
+
+def is_valid(value: Optional[float]) -> bool:
+    """Whether a stored smap_value is an observation rather than missing or a fill value"""
+    # NaN compares false with everything, so it isn't valid either
+    return value is not None and VALID_RANGE[0] <= value <= VALID_RANGE[1]
@@ -0,0 +1,257 @@
+import os
+import ssl
+import sqlite3
+import logging
+import smtplib
+import time
+import argparse
+from email import message_from_string, policy
+from email.message import EmailMessage
+from string import Template
+from typing import Dict, Optional
+
+"""
+Optional email notifications for operational events, configured from the environment:
+
+    OPENFLOW_SMTP_HOST      SMTP server; notifications are disabled when unset
+    OPENFLOW_SMTP_PORT      Default 587, using STARTTLS unless OPENFLOW_SMTP_SSL is set
+    OPENFLOW_SMTP_SSL       Connect with implicit TLS (usually port 465)
+    OPENFLOW_SMTP_USER / OPENFLOW_SMTP_PASSWORD
+    OPENFLOW_SMTP_FROM      Sender address
+    OPENFLOW_SMTP_TO        Comma-separated recipients
+    OPENFLOW_OUTBOX_DB      SQLite database for the outbox (default OPENFLOW_DB_PATH, then
+                            /var/lib/openflow/data.db when that directory exists)
+
+With an outbox database, every notification is stored before it is sent, and one
+that still fails after the immediate retries stays pending for `notify.py --dispatch`
+(run from cron) to retry with backoff, until it is delivered or dead-lettered.
+"""
+
+logger = logging.getLogger(__name__)
+
+SEND_ATTEMPTS = 3
+RETRY_DELAY = 5.0  # Seconds, doubled after each failed attempt
+DEFAULT_OUTBOX_DB = '/var/lib/openflow/data.db'
+# Dispatcher passes before a pending message is dead-lettered
+MAX_DISPATCHES = int(os.getenv('OPENFLOW_OUTBOX_MAX_ATTEMPTS', '10'))
+DISPATCH_DELAY = 60.0  # Seconds, doubled after each failed dispatcher pass
+MAX_BACKOFF = 6 * 3600.0
+
+TEMPLATES = {
+    'ingestion_failed': (
+        Template("OpenFlow ingestion failed for $start_date to $end_date"),
+        Template("The scheduled SMAP ingestion for $start_date to $end_date failed at $time.\n\n"
+                 "Error: $error\n\nSee $log_path for details.\n")
+    ),
+    'ingestion_quarantined': (
+        Template("OpenFlow ingestion run $run_id quarantined"),
+        Template("Ingestion run $run_id ($rows rows) deviated from recent runs at $time and is "
+                 "withheld from the API:\n\n$reasons\n\n"
+                 "Publish it with `anomaly.py approve $run_id` or discard it with `anomaly.py rollback $run_id`.\n")
+    ),
+    'integrity_mismatch': (
+        Template("OpenFlow stored data changed for $count dates"),
+        Template("Verification at $time found stored rows in $db_path that no longer match what was "
+                 "published:\n\n$dates\n\nThe dates are marked suspect; restore them from backup or "
+                 "re-ingest them, then run `integrity.py baseline` or a fresh ingestion to re-record them.\n")
+    ),
+    'moisture_alert': (
+        Template("OpenFlow alert: $location soil moisture $comparison $threshold"),
+        Template("Soil moisture at $location was $value m3/m3 on $date, $comparison the alert threshold "
+                 "of $threshold.\n\nAlert $alert_id won't send again for $cooldown_hours hours. Change or "
+                 "remove it with `alerts.py`.\n")
+    ),
+    'test': (
+        Template("OpenFlow test email"),
+        Template("Email notifications are configured correctly (sent $time).\n")
+    )
+}
+
+
+def configured() -> bool:
+    """Whether enough SMTP settings are present to send anything"""
+    return bool(os.getenv('OPENFLOW_SMTP_HOST') and os.getenv('OPENFLOW_SMTP_FROM')
+                and os.getenv('OPENFLOW_SMTP_TO'))
+
+
+def render(kind: str, to: Optional[str] = None, **values) -> EmailMessage:
+    """Build a message from one of the named templates, to OPENFLOW_SMTP_TO unless given recipients"""
+    subject, body = TEMPLATES[kind]
+    values.setdefault('time', time.strftime('%Y-%m-%d %H:%M:%S %Z'))
+    message = EmailMessage()
+    message['Subject'] = subject.safe_substitute(values)
+    message['From'] = os.getenv('OPENFLOW_SMTP_FROM')
+    message['To'] = to or os.getenv('OPENFLOW_SMTP_TO')
+    message.set_content(body.safe_substitute(values))
+    return message
+
+
+def _send(message: EmailMessage):
+    host = os.getenv('OPENFLOW_SMTP_HOST')
+    use_ssl = bool(os.getenv('OPENFLOW_SMTP_SSL'))
+    port = int(os.getenv('OPENFLOW_SMTP_PORT', '465' if use_ssl else '587'))
+    context = ssl.create_default_context()
+    if use_ssl:
+        smtp = smtplib.SMTP_SSL(host, port, timeout=30, context=context)
+    else:
+        smtp = smtplib.SMTP(host, port, timeout=30)
+    with smtp:
+        if not use_ssl:
+            smtp.starttls(context=context)
+        if os.getenv('OPENFLOW_SMTP_USER'):
+            smtp.login(os.getenv('OPENFLOW_SMTP_USER'), os.getenv('OPENFLOW_SMTP_PASSWORD', ''))
+        smtp.send_message(message)
+
+
+def create_outbox(conn: sqlite3.Connection):
+    conn.execute('''
+        CREATE TABLE IF NOT EXISTS outbox (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            kind TEXT,
+            message TEXT,           -- Rendered RFC 5322 message
+            state TEXT,             -- pending, sent, dead
+            attempts INTEGER,       -- Failed dispatcher passes
+            next_attempt REAL,      -- Unix time
+            last_error TEXT,
+            created_at REAL
+        )
+    ''')
+
+
+def _store(conn: sqlite3.Connection, kind: str, message: EmailMessage, next_attempt: float) -> int:
+    create_outbox(conn)
+    return conn.execute('''INSERT INTO outbox (kind, message, state, attempts, next_attempt, created_at)
+                           VALUES (?, ?, 'pending', 0, ?, ?)''',
+                        (kind, message.as_string(), next_attempt, time.time())).lastrowid
+
+
+def enqueue(conn: sqlite3.Connection, kind: str, to: Optional[str] = None, **values) -> int:
+    """Store a notification as pending without committing, so it joins the caller's transaction"""
+    return _store(conn, kind, render(kind, to, **values), time.time())
+
+
+def _outbox() -> Optional[sqlite3.Connection]:
+    """Connection to the outbox database, None where there is nowhere to keep one"""
+    path = os.getenv('OPENFLOW_OUTBOX_DB') or os.getenv('OPENFLOW_DB_PATH') or DEFAULT_OUTBOX_DB
+    return sqlite3.connect(path) if os.path.isdir(os.path.dirname(os.path.abspath(path))) else None
+
+
+def _attempt(message: EmailMessage) -> Optional[str]:
+    """Send once, returning the error instead of raising"""
+    try:
+        _send(message)
+        return None
+    except (OSError, smtplib.SMTPException) as e:
+        return str(e) or type(e).__name__
+
+
+def _record(conn: sqlite3.Connection, outbox_id: int, error: Optional[str]):
+    """Mark a message sent, or schedule its next dispatcher pass with exponential backoff"""
+    if error is None:
+        conn.execute("UPDATE outbox SET state = 'sent', last_error = NULL WHERE id = ?", (outbox_id,))
+        return
+    attempts = conn.execute('SELECT attempts FROM outbox WHERE id = ?', (outbox_id,)).fetchone()[0] + 1
+    state = 'dead' if attempts >= MAX_DISPATCHES else 'pending'
+    conn.execute('UPDATE outbox SET state = ?, attempts = ?, next_attempt = ?, last_error = ? WHERE id = ?',
+                 (state, attempts, time.time() + min(MAX_BACKOFF, DISPATCH_DELAY * 2 ** attempts), error, outbox_id))
+    if state == 'dead':
+        logger.error(f"Outbox message {outbox_id} dead-lettered after {attempts} attempts: {error}")
+
+
+def dispatch(conn: sqlite3.Connection) -> Dict[str, int]:
+    """Try every pending message that is due once, returning counts of sent and failed"""
+    create_outbox(conn)
+    due = conn.execute("SELECT id, message FROM outbox WHERE state = 'pending' AND next_attempt <= ? ORDER BY id",
+                       (time.time(),)).fetchall()
+    counts = {'sent': 0, 'failed': 0}
+    for outbox_id, raw in due:
+        error = _attempt(message_from_string(raw, policy=policy.default))
+        with conn:
+            _record(conn, outbox_id, error)
+        counts['failed' if error else 'sent'] += 1
+    return counts
+
+
+def notify(kind: str, **values) -> bool:
+    """Send a templated notification with retries; never raises, returns whether it was sent"""
+    if not configured():
+        logger.debug(f"Email not configured, skipping {kind} notification")
+        return False
+
+    message = render(kind, **values)
+    outbox, outbox_id = None, None
+    try:
+        outbox = _outbox()
+        if outbox:
+            with outbox:
+                # Stored before sending so a crash mid-send leaves it for the dispatcher
+                outbox_id = _store(outbox, kind, message, time.time() + DISPATCH_DELAY)
+    except sqlite3.Error as e:
+        # Still worth trying to send; it just won't survive a failure
+        logger.error(f"Could not store {kind} notification in the outbox: {e}")
+
+    delay = RETRY_DELAY
+    error = None
+    for attempt in range(1, SEND_ATTEMPTS + 1):
+        error = _attempt(message)
+        if error is None:
+            logger.info(f"Sent {kind} email to {message['To']}")
+            break
+        logger.error(f"Email attempt {attempt}/{SEND_ATTEMPTS} failed: {error}")
+        if attempt < SEND_ATTEMPTS:
+            time.sleep(delay)
+            delay *= 2
+
+    if outbox_id is not None:
+        try:
+            with outbox:
+                # Immediate retries don't count against the dispatcher's budget
+                if error is None:
+                    _record(outbox, outbox_id, None)
+                else:
+                    outbox.execute('UPDATE outbox SET last_error = ? WHERE id = ?', (error, outbox_id))
+        except sqlite3.Error as e:
+            logger.error(f"Could not update outbox message {outbox_id}: {e}")
+    if outbox:
+        outbox.close()
+    return error is None
+
+
+def main():
+    logging.basicConfig(level=logging.INFO)
+    parser = argparse.ArgumentParser(description="Send a test email to verify SMTP configuration, or manage the outbox")
+    actions = parser.add_mutually_exclusive_group()
+    actions.add_argument('--dispatch', action='store_true', help="Retry pending outbox messages that are due")
+    actions.add_argument('--dead', action='store_true', help="List dead-lettered messages")
+    actions.add_argument('--retry-dead', action='store_true', help="Return dead-lettered messages to pending")
+    args = parser.parse_args()
+    if not (args.dispatch or args.dead or args.retry_dead):
+        if not configured():
+            parser.error("set OPENFLOW_SMTP_HOST, OPENFLOW_SMTP_FROM and OPENFLOW_SMTP_TO")
+        if not notify('test'):
+            raise SystemExit(1)
+        return
+
+    conn = _outbox()
+    if conn is None:
+        parser.error("no outbox database; set OPENFLOW_OUTBOX_DB")
+    try:
+        create_outbox(conn)
+        if args.dispatch:
+            if configured():
+                logger.info(f"Outbox dispatch: {dispatch(conn)}")
+        elif args.dead:
+            for row in conn.execute("SELECT id, kind, attempts, last_error, datetime(created_at, 'unixepoch') "
+                                    "FROM outbox WHERE state = 'dead' ORDER BY id"):
+                print(*row, sep='\t')
+        else:
+            with conn:
+                count = conn.execute("UPDATE outbox SET state = 'pending', attempts = 0, next_attempt = 0 "
+                                     "WHERE state = 'dead'").rowcount
+            logger.info(f"Returned {count} dead-lettered messages to pending")
+    finally:
+        conn.close()
+
+
+if __name__ == "__main__":
+    main()
@@ -1,25 +1,1015 @@
+import csv
+import io
+import os
 import sqlite3
 import json
-from bottle import Bottle, request, response
+import sys
+import zlib
+import logging
+import math
+from datetime import date, datetime, timedelta, timezone
+from bottle import Bottle, HTTPResponse, request, response
 from waitress import serve
 
+from agro import WeatherError, plant_available_water, reference_et0
+import crops
+import cursors
+from coalesce import SingleFlight
+from climatology import MIN_OBSERVATIONS, QUANTILES, WINDOW_DAYS, day_of_year, percentile_of
+from dbtiming import QueryBudgetExceeded, connect
+from formats import MAX_SIGNIFICANT_DIGITS, SIGNIFICANT_DIGITS, encode, negotiate, round_significant
+from grid import GRIDS, PRODUCT_GRIDS
+from integrity import suspect_dates
+from limits import MAX_IN_FLIGHT, RETRY_AFTER, ROUTE_LIMITS, ConcurrencyLimiter, parse_route_limits
+from moisture import VALID_MOISTURE, is_valid
+from selfcheck import run_checks
+from smaptime import PRODUCT_REVISIT_DAYS
+from timeseries import (FILL_METHODS, correlation, drydown_forecast, fill_gaps, last_recharge, linear_trend,
+                        water_balance)
+import validation
+from validation import InvalidInput, pointer
+
 app = Bottle()
+limiter = ConcurrencyLimiter(MAX_IN_FLIGHT, parse_route_limits(ROUTE_LIMITS))
+flights = SingleFlight()
+# Streamed bodies can only be consumed once, so their requests are never shared
+UNCOALESCED_ROUTES = {'/export'}
 DB_PATH = '{{ db_path }}'
+EXPORT_COLUMNS = ['date', 'location', 'smap_value', 'vegdri_value']
+UNITS = {'fraction': 'm3/m3', 'percent': '%'}
+MAX_PRECISION = 6
+PRODUCT = {'short_name': 'SPL3SMP_E', 'version': '006', 'revisit_days': PRODUCT_REVISIT_DAYS['SPL3SMP_E']}
+MAX_FILL_GAP = 10
+MAX_ET0_DAYS = 366
+MAX_FORECAST_DAYS = 7
+FORECAST_TRAINING_DAYS = 30
+ET0_FIELDS = ('latitude', 'elevation', 'tmin', 'tmax', 'wind_speed', 'wind_height', 'rh_min', 'rh_max',
+              'rh_mean', 'tdew', 'solar_radiation', 'sunshine_hours')
+MAX_TREND_WINDOW = 365
+# Slopes within +/- this many m3/m3 per day are reported as stable
+TREND_STABLE_SLOPE = float(os.getenv('OPENFLOW_TREND_STABLE_SLOPE', '0.002'))
+# Highest percentile in each drought class, US Drought Monitor style
+DROUGHT_CLASSES = ('D0', 'D1', 'D2', 'D3', 'D4')
+DROUGHT_THRESHOLDS = dict(zip(DROUGHT_CLASSES, (float(p) for p in
+                                                os.getenv('OPENFLOW_DROUGHT_THRESHOLDS', '30,20,10,5,2').split(','))))
+MAX_DROUGHT_LOOKBACK = 365
+# Rise in m3/m3 within two days that counts as recharge, and the range a client may ask for
+DEFAULT_RECHARGE_DELTA = 0.03
+RECHARGE_DELTA_RANGE = (0.005, 0.3)
+MAX_RECHARGE_WINDOW = 365
+MAX_WATER_BALANCE_DAYS = 366
+# Depth of soil SMAP senses, for converting a moisture change to mm of stored water
+SENSING_DEPTH_MM = 50
+# Keyset pagination order; cursors encode the last row's values of these columns. rowid
+# breaks ties, since nothing stops two rows sharing a date and location
+PAGE_KEY = ('date', 'location', 'rowid')
+DEFAULT_PAGE_SIZE = 1000
+MAX_PAGE_SIZE = 10000
+
+def _bad_request(message):
+    """Build a JSON 400 response"""
+    return HTTPResponse(json.dumps({'error': message}), status=400,
+                        headers={'Content-Type': 'application/json'})
+
+def _invalid(error):
+    """Build a JSON 400 response locating the failing body value"""
+    return HTTPResponse(json.dumps(error.to_dict()), status=400,
+                        headers={'Content-Type': 'application/json'})
+
+def _json_body():
+    """Decode a JSON request body; None unless the client sent application/json"""
+    if not request.content_type.lower().startswith('application/json'):
+        return None
+    try:
+        return validation.decode(request.body.read())
+    except InvalidInput as e:
+        raise _invalid(e)
+
+def _db_budget(callback):
+    """Plugin turning a request that used up its database time budget into a 503"""
+    def wrapper(*args, **kwargs):
+        try:
+            return callback(*args, **kwargs)
+        except QueryBudgetExceeded:
+            raise HTTPResponse(json.dumps({'error': 'query too expensive, narrow your filters'}), status=503,
+                               headers={'Content-Type': 'application/json'})
+    return wrapper
+
+app.install(_db_budget)
+
+def _coalesce(callback):
+    """Plugin letting identical concurrent GET requests share one response"""
+    def wrapper(*args, **kwargs):
+        if request.method != 'GET' or request.route.rule in UNCOALESCED_ROUTES:
+            return callback(*args, **kwargs)
+        key = (request.path, tuple(sorted(request.query.allitems())), request.headers.get('Accept'))
+
+        def lead():
+            body = callback(*args, **kwargs)
+            return body, response.status_line, list(response.headerlist)
+        body, status, headers = flights.do(key, lead)
+        # Followers never ran the route, so copy the leader's status and headers
+        response.status = status
+        for name, value in headers:
+            response.set_header(name, value)
+        return body
+    return wrapper
+
+# Installed before the concurrency limit so waiting followers don't hold a slot
+app.install(_coalesce)
+
+def _concurrency(callback):
+    """Plugin rejecting requests with a 503 while the server or their route is at capacity"""
+    def wrapper(*args, **kwargs):
+        route = request.route.rule
+        if not limiter.acquire(route):
+            raise HTTPResponse(json.dumps({'error': 'server busy, retry shortly'}), status=503,
+                               headers={'Content-Type': 'application/json', 'Retry-After': str(RETRY_AFTER)})
+        try:
+            body = callback(*args, **kwargs)
+        except BaseException:
+            limiter.release(route)
+            raise
+        if hasattr(body, '__next__'):
+            # Streamed exports hold their slot until the last chunk is sent
+            return limiter.hold(body, route)
+        limiter.release(route)
+        return body
+    return wrapper
+
+app.install(_concurrency)
+
+def _respond(payload):
+    """Serialize a payload in the format the client negotiated"""
+    try:
+        media_type = negotiate(request.query.get('format'), request.headers.get('Accept'))
+    except ValueError as e:
+        raise _bad_request(str(e))
+    response.content_type = media_type
+    return encode(payload, media_type, _digits_option())
+
+def _grid_option():
+    """The grid named by the grid query parameter, defaulting to the served product's grid"""
+    name = request.query.get('grid', PRODUCT_GRIDS[PRODUCT['short_name']])
+    if name not in GRIDS:
+        raise _bad_request(f"grid must be one of: {', '.join(GRIDS)}")
+    return GRIDS[name]
+
+def _parse_coordinate(name, value, limit):
+    """Parse a latitude/longitude query parameter within +/- limit degrees"""
+    try:
+        value = float(value)
+    except (TypeError, ValueError):
+        raise _bad_request(f"{name} must be a number")
+    if not -limit <= value <= limit:
+        raise _bad_request(f"{name} must be between -{limit} and {limit}")
+    return value
+
+def _value_options():
+    """Parse and validate the unit/precision query parameters"""
+    unit = request.query.get('unit', 'fraction')
+    if unit not in UNITS:
+        raise _bad_request(f"unit must be one of: {', '.join(UNITS)}")
+
+    precision = request.query.get('precision')
+    if precision is not None:
+        try:
+            precision = int(precision)
+        except ValueError:
+            raise _bad_request("precision must be an integer")
+        if not 0 <= precision <= MAX_PRECISION:
+            raise _bad_request(f"precision must be between 0 and {MAX_PRECISION}")
+    return unit, precision
+
+def _digits_option():
+    """Parse the digits query parameter: significant digits kept in serialized floats"""
+    digits = request.query.get('digits')
+    if digits is None:
+        return SIGNIFICANT_DIGITS
+    try:
+        digits = int(digits)
+    except ValueError:
+        raise _bad_request("digits must be an integer")
+    if not 1 <= digits <= MAX_SIGNIFICANT_DIGITS:
+        raise _bad_request(f"digits must be between 1 and {MAX_SIGNIFICANT_DIGITS}")
+    return digits
+
+def _parse_date(name, value):
+    """Parse a YYYY-MM-DD query parameter"""
+    try:
+        return date.fromisoformat(value)
+    except (TypeError, ValueError):
+        raise _bad_request(f"{name} must be a date in YYYY-MM-DD format")
+
+def _fill_options():
+    """Parse and validate the gap-filling query parameters"""
+    fill = request.query.get('fill', 'none')
+    if fill not in FILL_METHODS:
+        raise _bad_request(f"fill must be one of: {', '.join(FILL_METHODS)}")
+
+    try:
+        max_gap = int(request.query.get('max_gap', 3))
+    except ValueError:
+        raise _bad_request("max_gap must be an integer")
+    if not 1 <= max_gap <= MAX_FILL_GAP:
+        raise _bad_request(f"max_gap must be between 1 and {MAX_FILL_GAP}")
+    return fill, max_gap
+
+def _page_options():
+    """Parse the limit/cursor pagination parameters; limit is None when the client isn't paging"""
+    limit, token = request.query.get('limit'), request.query.get('cursor')
+    if limit is None and token is None:
+        return None, None
+    try:
+        limit = int(limit) if limit is not None else DEFAULT_PAGE_SIZE
+    except ValueError:
+        raise _bad_request("limit must be an integer")
+    if not 1 <= limit <= MAX_PAGE_SIZE:
+        raise _bad_request(f"limit must be between 1 and {MAX_PAGE_SIZE}")
+    try:
+        after = cursors.decode(PAGE_KEY, token) if token else None
+    except ValueError as e:
+        raise _bad_request(str(e))
+    return limit, after
+
+def _keyset(clauses, params, limit, after):
+    """Extend a query's filters for keyset pagination, returning the ORDER BY/LIMIT suffix"""
+    if after:
+        clauses.append(f"({', '.join(PAGE_KEY)}) > ({', '.join('?' * len(PAGE_KEY))})")
+        params.extend(after)
+    if limit is None:
+        return f"ORDER BY {', '.join(PAGE_KEY)}"
+    # One extra row tells whether another page follows
+    params.append(limit + 1)
+    return f"ORDER BY {', '.join(PAGE_KEY)} LIMIT ?"
+
+def _filled_rows(data, start, end, fill, max_gap):
+    """Expand each location's rows into a gap-filled daily series"""
+    by_location = {}
+    for row_date, location, smap_value, vegdri_value, _ in data:
+        observed = by_location.setdefault(location, {})
+        observed[date.fromisoformat(row_date)] = (smap_value, vegdri_value)
+
+    rows = []
+    for location, observed in by_location.items():
+        # Fill values are gaps to fill, never neighbours to interpolate from or carry forward
+        smap = {day: values[0] for day, values in observed.items()
+                if is_valid(values[0])}
+        for point in fill_gaps(smap, start, end, fill, max_gap):
+            rows.append({
+                'date': point['date'].isoformat(),
+                'location': location,
+                'smap_value': point['value'],
+                'vegdri_value': observed.get(point['date'], (None, None))[1],
+                'filled': point['filled']
+            })
+    return rows
+
+def _use_paw():
+    """Whether the client asked for plant-available water alongside moisture"""
+    return request.query.get('paw', 'false').lower() in ('1', 'true', 'yes')
+
+def _soil_properties(conn):
+    """(field capacity, wilting point) per location from soil_properties"""
+    try:
+        return {location: (fc, wp) for location, fc, wp in conn.execute(
+            'SELECT location, field_capacity, wilting_point FROM soil_properties')}
+    except sqlite3.OperationalError:
+        # No soil data has been imported yet
+        return {}
+
+def _add_paw(conn, rows, precision):
+    """Add plant-available water from soil_properties, with a reason wherever it is null"""
+    soils = _soil_properties(conn)
+    for row in rows:
+        paw, reason = None, None
+        if row['location'] not in soils:
+            reason = 'no soil properties for location'
+        elif row['smap_value'] is None:
+            reason = 'no soil moisture value'
+        elif not is_valid(row['smap_value']):
+            reason = 'soil moisture is a fill value'
+        else:
+            paw = plant_available_water(row['smap_value'], *soils[row['location']])
+            if paw is None:
+                reason = 'invalid soil properties'
+        row['paw'] = round(paw, precision) if paw is not None and precision is not None else paw
+        if reason:
+            row['paw_reason'] = reason
+
+def _classification_option():
+    """Crop band for ?classification= and ?stage=, or None when classification wasn't requested"""
+    crop = request.query.get('classification')
+    if not crop:
+        return None
+    conn = connect(DB_PATH)
+    try:
+        band = crops.lookup(conn, crop, request.query.get('stage', crops.DEFAULT_STAGE))
+        options = crops.available(conn) if band is None else None
+    finally:
+        conn.close()
+    if band is None:
+        raise _bad_request(f"classification must be one of: {', '.join(options)}" if options
+                           else "no crop bands are configured")
+    return band
+
+def _add_classification(conn, rows, band):
+    """Place each stored fraction in a crop band, by PAW where the location has soil properties"""
+    soils = _soil_properties(conn)
+    for row in rows:
+        value, soil = row['smap_value'], soils.get(row['location'])
+        if not is_valid(value):
+            row['classification'], basis = None, None
+        elif soil and soil[0] > soil[1]:
+            fc, wp = soil
+            # Not clamped like plant_available_water, so moisture past field capacity reads as above
+            row['classification'], basis = crops.classify((value - wp) / (fc - wp), band['paw_lower'],
+                                                          band['paw_upper']), 'paw'
+        else:
+            row['classification'], basis = crops.classify(value, band['vwc_lower'], band['vwc_upper']), 'volumetric'
+        row['classification_basis'] = basis
+
+def _use_percentile():
+    """Whether the client asked for each value's percentile against the location's climatology"""
+    return request.query.get('percentile', 'false').lower() in ('1', 'true', 'yes')
+
+def _climatology(conn, location):
+    """{day of year: (quantiles, years, computed_at)} for a location, empty before climatology.py has run"""
+    columns = ', '.join(f'p{q}' for q in QUANTILES)
+    try:
+        return {doy: (dict(zip(QUANTILES, values)), years, computed_at)
+                for doy, years, computed_at, *values in conn.execute(
+                    f'SELECT day_of_year, years, computed_at, {columns} FROM climatology WHERE location = ?',
+                    (location,))}
+    except sqlite3.OperationalError:
+        # Climatology has never been computed
+        return {}
+
+def _add_percentile(conn, rows):
+    """Add each value's percentile among the location's history for that time of year, with a reason when null"""
+    baselines = {}
+    for row in rows:
+        location, value = row['location'], row['smap_value']
+        if location not in baselines:
+            baselines[location] = _climatology(conn, location)
+        baseline = baselines[location].get(day_of_year(date.fromisoformat(row['date'])))
+        percentile, reason = None, None
+        if not is_valid(value):
+            reason = 'no valid soil moisture value'
+        elif baseline is None:
+            # climatology.py only stores days of year with enough observations in their window
+            reason = f'fewer than {MIN_OBSERVATIONS} historical observations for this time of year'
+        else:
+            percentile = percentile_of(value, baseline[0])
+            if percentile is None:
+                reason = 'climatology is incomplete for this time of year'
+        row['percentile'] = round(percentile, 1) if percentile is not None else None
+        if reason:
+            row['percentile_reason'] = reason
+
+def _use_envelope():
+    """Whether the client opted in to the {meta, data} response envelope"""
+    return request.query.get('envelope', 'false').lower() in ('1', 'true', 'yes')
+
+def _envelope(data, unit, latest_date, next_cursor=None):
+    """Wrap result rows with metadata describing units, product, and freshness"""
+    return {
+        'meta': {
+            'units': UNITS[unit],
+            'product': PRODUCT,
+            'latest_date': latest_date,
+            'count': len(data),
+            'truncated': next_cursor is not None,
+            'next_cursor': next_cursor
+        },
+        'data': data
+    }
+
+def _product_headers(unit):
+    """Carry envelope metadata in headers for formats that can't embed it"""
+    response.set_header('X-OpenFlow-Units', UNITS[unit])
+    response.set_header('X-OpenFlow-Product', f"{PRODUCT['short_name']}.{PRODUCT['version']}")
+
+def _format_moisture(value, unit, precision):
+    """Convert a stored volumetric fraction for output; stored data is never modified"""
+    if value is None:
+        return None
+    if unit == 'percent':
+        value = value * 100
+    # Rounding is the last step so it applies to the final converted value
+    return round(value, precision) if precision is not None else value
 
 @app.route('/data')
 def get_data():
     start_date = request.query.get('start_date')
     end_date = request.query.get('end_date')
-    conn = sqlite3.connect(DB_PATH)
+    unit, precision = _value_options()
+    fill, max_gap = _fill_options()
+    limit, after = _page_options()
+    band = _classification_option()
+    if fill != 'none':
+        if limit is not None:
+            raise _bad_request("fill can't be combined with limit or cursor")
+        start, end = _parse_date('start_date', start_date), _parse_date('end_date', end_date)
+    clauses, params = ['date BETWEEN ? AND ?'], [start_date, end_date]
+    # Unpaged requests keep their historical unordered results
+    suffix = _keyset(clauses, params, limit, after) if limit is not None else ''
+    conn = connect(DB_PATH)
     cursor = conn.cursor()
-    cursor.execute('''SELECT * FROM processed_data 
-                      WHERE date BETWEEN ? AND ?''', (start_date, end_date))
+    cursor.execute(f"""SELECT date, location, smap_value, vegdri_value, rowid FROM processed_data
+                       WHERE {' AND '.join(clauses)} {suffix}""", params)
     data = cursor.fetchall()
-    conn.close()
+    next_cursor = None
+    if limit is not None and len(data) > limit:
+        data = data[:limit]
+        next_cursor = cursors.encode(PAGE_KEY, (data[-1][0], data[-1][1], data[-1][4]))
+    latest_date = conn.execute('SELECT MAX(date) FROM processed_data').fetchone()[0] if _use_envelope() else None
     
-    response.content_type = 'application/json'
-    return json.dumps([{'date': row[0], 'location': row[1], 'smap_value': row[2], 'vegdri_value': row[3]} 
-                       for row in data])
+    if fill == 'none':
+        rows = [{'date': row[0], 'location': row[1], 'smap_value': row[2], 'vegdri_value': row[3]}
+                for row in data]
+    else:
+        rows = _filled_rows(data, start, end, fill, max_gap)
+    if _use_paw():
+        # Computed from the stored fraction before any unit conversion or rounding
+        _add_paw(conn, rows, precision)
+    if band:
+        _add_classification(conn, rows, band)
+    if _use_percentile():
+        _add_percentile(conn, rows)
+    conn.close()
+
+    _product_headers(unit)
+    if next_cursor:
+        response.set_header('X-OpenFlow-Next-Cursor', next_cursor)
+    for row in rows:
+        row['smap_value'] = _format_moisture(row['smap_value'], unit, precision)
+    return _respond(_envelope(rows, unit, latest_date, next_cursor) if _use_envelope() else rows)
+
+@app.route('/export')
+def export_data():
+    """Stream processed data as CSV, optionally filtered by date range"""
+    start_date = request.query.get('start_date')
+    end_date = request.query.get('end_date')
+    unit, precision = _value_options()
+    digits = _digits_option()
+    limit, after = _page_options()
+
+    clauses, params = [], []
+    if start_date:
+        clauses.append('date >= ?')
+        params.append(start_date)
+    if end_date:
+        clauses.append('date <= ?')
+        params.append(end_date)
+    suffix = _keyset(clauses, params, limit, after)
+    where = f"WHERE {' AND '.join(clauses)}" if clauses else ''
+
+    filename = f"openflow_{start_date or 'start'}_{end_date or 'end'}.csv"
+    gzipped = 'gzip' in request.headers.get('Accept-Encoding', '')
+    response.content_type = 'text/csv'
+    response.set_header('Content-Disposition', f'attachment; filename="{filename}"')
+    _product_headers(unit)
+    if gzipped:
+        response.set_header('Content-Encoding', 'gzip')
+
+    rows = _export_rows(f"SELECT date, location, smap_value, vegdri_value, rowid FROM processed_data {where} {suffix}",
+                        params, unit, precision, digits, limit)
+    return _gzip_stream(rows) if gzipped else rows
+
+def _export_rows(query, params, unit, precision, digits, limit=None):
+    """Yield CSV-encoded chunks straight from the cursor without materializing the result"""
+    # Autocommit mode keeps the read out of any write-blocking transaction. Exports stream
+    # at the client's pace, so they aren't held to the per-request time budget
+    conn = connect(DB_PATH, budget_ms=None, isolation_level=None)
+    buffer = io.StringIO()
+    writer = csv.writer(buffer, lineterminator='\n')
+    count = 0
+    next_cursor = None
+    try:
+        writer.writerow(EXPORT_COLUMNS)
+        for date, location, smap_value, vegdri_value, rowid in conn.execute(query, params):
+            if count == limit:
+                # Headers are gone by now, so the cursor goes in the trailer
+                next_cursor = cursors.encode(PAGE_KEY, last_key)
+                break
+            last_key = (date, location, rowid)
+            writer.writerow([date, location, round_significant(_format_moisture(smap_value, unit, precision), digits),
+                             round_significant(vegdri_value, digits)])
+            count += 1
+            if count % 1000 == 0:
+                yield buffer.getvalue()
+                buffer.seek(0)
+                buffer.truncate()
+        buffer.write(f"# rows: {count}\n")
+        if next_cursor:
+            buffer.write(f"# next_cursor: {next_cursor}\n")
+        buffer.write(f"# generated: {datetime.now(timezone.utc).isoformat()}\n")
+        yield buffer.getvalue()
+    finally:
+        # Runs on normal completion and when the server closes the iterator on client disconnect
+        conn.close()
+
+def _gzip_stream(chunks):
+    """Gzip-compress a stream of text chunks incrementally"""
+    compressor = zlib.compressobj(wbits=16 + zlib.MAX_WBITS)
+    try:
+        for chunk in chunks:
+            data = compressor.compress(chunk.encode('utf-8'))
+            if data:
+                yield data
+        yield compressor.flush()
+    finally:
+        chunks.close()
+
+@app.route('/trend')
+def get_trend():
+    """Least-squares drying/wetting slope for a location over a trailing window"""
+    location = request.query.get('location')
+    if not location:
+        raise _bad_request("location is required")
+    try:
+        window = int(request.query.get('window', 14))
+    except ValueError:
+        raise _bad_request("window must be an integer number of days")
+    if not 3 <= window <= MAX_TREND_WINDOW:
+        raise _bad_request(f"window must be between 3 and {MAX_TREND_WINDOW} days")
+    unit, precision = _value_options()
+    end_date = request.query.get('end_date')
+    if end_date is not None:
+        _parse_date('end_date', end_date)
+
+    conn = connect(DB_PATH)
+    if end_date is None:
+        end_date = conn.execute(f'SELECT MAX(date) FROM processed_data WHERE location = ? AND {VALID_MOISTURE}',
+                                (location,)).fetchone()[0]
+    rows = []
+    if end_date is not None:
+        end = date.fromisoformat(end_date)
+        start = end - timedelta(days=window - 1)
+        rows = conn.execute(f'''SELECT date, smap_value FROM processed_data
+                                WHERE location = ? AND date BETWEEN ? AND ? AND {VALID_MOISTURE}''',
+                            (location, start.isoformat(), end.isoformat())).fetchall()
+    conn.close()
+
+    result = {'location': location, 'window_days': window, 'end_date': end_date, 'units': f"{UNITS[unit]}/day"}
+    trend = linear_trend({date.fromisoformat(row_date): value for row_date, value in rows})
+    if trend is None:
+        result.update({'status': 'insufficient_data', 'observations': len(rows),
+                       'slope_per_day': None, 'r_squared': None, 'label': None})
+        return _respond(result)
+
+    slope = trend['slope']
+    if slope < -TREND_STABLE_SLOPE:
+        label = 'drying'
+    elif slope > TREND_STABLE_SLOPE:
+        label = 'wetting'
+    else:
+        label = 'stable'
+    result.update({
+        'status': 'ok',
+        'observations': trend['observations'],
+        'slope_per_day': _format_moisture(slope, unit, precision),
+        'r_squared': trend['r_squared'],
+        'label': label
+    })
+    return _respond(result)
+
+@app.route('/compare')
+def compare_locations():
+    """Align two locations' series and summarize how they differ"""
+    location_a = request.query.get('location_a')
+    location_b = request.query.get('location_b')
+    if not location_a or not location_b:
+        raise _bad_request("location_a and location_b are required")
+    if location_a == location_b:
+        raise _bad_request("location_a and location_b refer to the same location")
+    start = _parse_date('start_date', request.query.get('start_date'))
+    end = _parse_date('end_date', request.query.get('end_date'))
+    unit, precision = _value_options()
+
+    conn = connect(DB_PATH)
+    # Duplicate (date, location) rows are averaged first, so each day pairs exactly once
+    daily = f'''SELECT date, AVG(smap_value) AS smap_value FROM processed_data
+                WHERE location = ? AND date BETWEEN ? AND ? AND {VALID_MOISTURE}
+                GROUP BY date'''
+    rows = conn.execute(f'''SELECT a.date, a.smap_value, b.smap_value
+                           FROM ({daily}) a JOIN ({daily}) b ON a.date = b.date
+                           ORDER BY a.date''',
+                        (location_a, start.isoformat(), end.isoformat(),
+                         location_b, start.isoformat(), end.isoformat())).fetchall()
+    conn.close()
+
+    values_a = [row[1] for row in rows]
+    values_b = [row[2] for row in rows]
+    differences = [a - b for a, b in zip(values_a, values_b)]
+
+    return _respond({
+        'location_a': location_a,
+        'location_b': location_b,
+        'units': UNITS[unit],
+        'summary': {
+            'paired_days': len(rows),
+            'mean_difference': _format_moisture(sum(differences) / len(differences), unit, precision)
+                               if differences else None,
+            'correlation': correlation(values_a, values_b)
+        },
+        'data': [{
+            'date': row_date,
+            'value_a': _format_moisture(a, unit, precision),
+            'value_b': _format_moisture(b, unit, precision),
+            'difference': _format_moisture(a - b, unit, precision)
+        } for row_date, a, b in rows]
+    })
+
+@app.route('/forecast')
+def get_forecast():
+    """Short-range dry-down forecast for a location from its recent history"""
+    location = request.query.get('location')
+    if not location:
+        raise _bad_request("location is required")
+    try:
+        days = int(request.query.get('days', 3))
+    except ValueError:
+        raise _bad_request("days must be an integer")
+    if not 1 <= days <= MAX_FORECAST_DAYS:
+        raise _bad_request(f"days must be between 1 and {MAX_FORECAST_DAYS}")
+    unit, precision = _value_options()
+
+    conn = connect(DB_PATH)
+    latest = conn.execute(f'SELECT MAX(date) FROM processed_data WHERE location = ? AND {VALID_MOISTURE}',
+                          (location,)).fetchone()[0]
+    rows, training_start = [], None
+    if latest is not None:
+        training_start = (date.fromisoformat(latest) - timedelta(days=FORECAST_TRAINING_DAYS - 1)).isoformat()
+        rows = conn.execute(f'''SELECT date, smap_value FROM processed_data
+                                WHERE location = ? AND date BETWEEN ? AND ? AND {VALID_MOISTURE}''',
+                            (location, training_start, latest)).fetchall()
+    conn.close()
+
+    result = {
+        'location': location,
+        'units': UNITS[unit],
+        'model': {
+            'type': 'exponential_drydown',
+            'training_start': training_start,
+            'training_end': latest,
+            'training_observations': len(rows)
+        }
+    }
+    model = drydown_forecast({date.fromisoformat(row_date): value for row_date, value in rows}, days)
+    if model is None:
+        result.update({'status': 'insufficient_data', 'forecast': []})
+        return _respond(result)
+
+    result['model'].update({'asymptote': _format_moisture(model['asymptote'], unit, precision),
+                            'decay_rate_per_day': model['decay_rate']})
+    result.update({
+        'status': 'ok',
+        'forecast': [{
+            'date': point['date'].isoformat(),
+            'forecast': True,
+            'value': _format_moisture(point['value'], unit, precision),
+            'lower': _format_moisture(point['lower'], unit, precision),
+            'upper': _format_moisture(point['upper'], unit, precision)
+        } for point in model['forecast']]
+    })
+    return _respond(result)
+
+@app.route('/last_recharge')
+def get_last_recharge():
+    """Most recent day a location got meaningfully wetter, and the days since, within a trailing window"""
+    location = request.query.get('location')
+    if not location:
+        raise _bad_request("location is required")
+    try:
+        delta = float(request.query.get('delta', DEFAULT_RECHARGE_DELTA))
+    except ValueError:
+        raise _bad_request("delta must be a number")
+    low, high = RECHARGE_DELTA_RANGE
+    if not low <= delta <= high:
+        raise _bad_request(f"delta must be between {low} and {high} m3/m3")
+    try:
+        window = int(request.query.get('window', 90))
+    except ValueError:
+        raise _bad_request("window must be an integer number of days")
+    if not 3 <= window <= MAX_RECHARGE_WINDOW:
+        raise _bad_request(f"window must be between 3 and {MAX_RECHARGE_WINDOW} days")
+    unit, precision = _value_options()
+
+    conn = connect(DB_PATH)
+    latest = conn.execute(f'SELECT MAX(date) FROM processed_data WHERE location = ? AND {VALID_MOISTURE}',
+                          (location,)).fetchone()[0]
+    rows = []
+    if latest is not None:
+        start = (date.fromisoformat(latest) - timedelta(days=window - 1)).isoformat()
+        rows = conn.execute(f'''SELECT date, smap_value FROM processed_data
+                                WHERE location = ? AND date BETWEEN ? AND ? AND {VALID_MOISTURE}''',
+                            (location, start, latest)).fetchall()
+    conn.close()
+
+    # Days are counted to the latest observation, since data arrives a few days behind
+    result = {'location': location, 'window_days': window, 'delta': delta, 'latest_date': latest,
+              'units': UNITS[unit]}
+    recharge = last_recharge({date.fromisoformat(row_date): value for row_date, value in rows}, delta)
+    if recharge is None:
+        result.update({'last_recharge': None, 'days_since': None, 'increase': None})
+    else:
+        result.update({
+            'last_recharge': recharge['date'].isoformat(),
+            'days_since': (date.fromisoformat(latest) - recharge['date']).days,
+            'increase': _format_moisture(recharge['increase'], unit, precision)
+        })
+    return _respond(result)
+
+@app.route('/completeness')
+def get_completeness():
+    """How much of a date range has valid observations for a location"""
+    location = request.query.get('location')
+    if not location:
+        raise _bad_request("location is required")
+    start = _parse_date('start_date', request.query.get('start_date'))
+    end = _parse_date('end_date', request.query.get('end_date'))
+    if end < start:
+        raise _bad_request("end_date must not be before start_date")
+    params = (location, start.isoformat(), end.isoformat())
+
+    conn = connect(DB_PATH)
+    observed = conn.execute(f'''SELECT COUNT(DISTINCT date) FROM processed_data
+                                WHERE location = ? AND date BETWEEN ? AND ? AND {VALID_MOISTURE}''',
+                            params).fetchone()[0]
+    # Bracket the observed days with sentinels just outside the range so leading and
+    # trailing gaps are measured the same way as gaps between observations
+    gap = conn.execute(f'''
+        WITH days AS (
+            SELECT date(?, '-1 day') AS date
+            UNION SELECT date FROM processed_data
+                  WHERE location = ? AND date BETWEEN ? AND ? AND {VALID_MOISTURE}
+            UNION SELECT date(?, '+1 day')
+        ), gaps AS (
+            SELECT LAG(date) OVER (ORDER BY date) AS previous, date FROM days
+        )
+        SELECT date(previous, '+1 day'), date(date, '-1 day'),
+               CAST(julianday(date) - julianday(previous) - 1 AS INTEGER) AS missing
+        FROM gaps WHERE previous IS NOT NULL
+        ORDER BY missing DESC, previous LIMIT 1
+    ''', (start.isoformat(),) + params + (end.isoformat(),)).fetchone()
+    suspect = suspect_dates(conn, start.isoformat(), end.isoformat())
+    conn.close()
+
+    days = (end - start).days + 1
+    # A cell only has a retrieval every few days, so a full record is one per revisit
+    expected = math.ceil(days / PRODUCT['revisit_days'])
+    return _respond({
+        'location': location,
+        'start_date': start.isoformat(),
+        'end_date': end.isoformat(),
+        'expected_days': days,
+        'revisit_days': PRODUCT['revisit_days'],
+        'expected_observations': expected,
+        'observed_days': observed,
+        'completeness': min(100.0, round(observed / expected * 100, 1)),
+        'longest_gap': {'days': gap[2], 'start': gap[0], 'end': gap[1]} if gap and gap[2] > 0 else None,
+        # Dates whose stored rows failed integrity verification since they were published
+        'suspect_dates': suspect
+    })
+
+@app.route('/water_balance')
+def get_water_balance():
+    """Daily moisture and precipitation for a location, aligned, with a naive running balance"""
+    location = request.query.get('location')
+    if not location:
+        raise _bad_request("location is required")
+    start = _parse_date('start_date', request.query.get('start_date'))
+    end = _parse_date('end_date', request.query.get('end_date'))
+    if end < start:
+        raise _bad_request("end_date must not be before start_date")
+    if (end - start).days + 1 > MAX_WATER_BALANCE_DAYS:
+        raise _bad_request(f"date range must not exceed {MAX_WATER_BALANCE_DAYS} days")
+    unit, precision = _value_options()
+    params = (location, start.isoformat(), end.isoformat())
+
+    conn = connect(DB_PATH)
+    moisture = conn.execute(f'''SELECT date, AVG(smap_value) FROM processed_data
+                                WHERE location = ? AND date BETWEEN ? AND ? AND {VALID_MOISTURE}
+                                GROUP BY date''', params).fetchall()
+    try:
+        precipitation = conn.execute('''SELECT date, precipitation FROM precipitation
+                                        WHERE location = ? AND date BETWEEN ? AND ?''', params).fetchall()
+    except sqlite3.OperationalError:
+        # No precipitation has been fetched yet (openmeteo.py)
+        precipitation = []
+    conn.close()
+
+    series = water_balance({date.fromisoformat(day): value for day, value in moisture},
+                           {date.fromisoformat(day): total for day, total in precipitation},
+                           start, end, SENSING_DEPTH_MM)
+    return _respond({
+        'location': location,
+        'start_date': start.isoformat(),
+        'end_date': end.isoformat(),
+        'units': {'moisture': UNITS[unit], 'precipitation': 'mm', 'balance': 'mm'},
+        'sensing_depth_mm': SENSING_DEPTH_MM,
+        'precipitation_days': len(precipitation),
+        'data': [{'date': row['date'].isoformat(),
+                  'moisture': _format_moisture(row['moisture'], unit, precision),
+                  'precipitation': row['precipitation'],
+                  'balance': round(row['balance'], 2) if row['balance'] is not None else None}
+                 for row in series]
+    })
+
+def _drought_class(percentile):
+    """Most severe class whose threshold the percentile falls within"""
+    if percentile is None:
+        return 'unclassified'
+    for name in reversed(DROUGHT_CLASSES):
+        if percentile <= DROUGHT_THRESHOLDS[name]:
+            return name
+    return 'none'
+
+@app.route('/drought')
+def get_drought():
+    """Drought class of a location's soil moisture percentile against its climatology"""
+    location = request.query.get('location')
+    if not location:
+        raise _bad_request("location is required")
+    unit, precision = _value_options()
+    day = request.query.get('date')
+    if day is not None:
+        _parse_date('date', day)
+
+    conn = connect(DB_PATH)
+    if day is None:
+        day = conn.execute(f'SELECT MAX(date) FROM processed_data WHERE location = ? AND {VALID_MOISTURE}',
+                           (location,)).fetchone()[0]
+    rows, baselines = [], {}
+    if day is not None:
+        end = date.fromisoformat(day)
+        start = end - timedelta(days=MAX_DROUGHT_LOOKBACK)
+        rows = conn.execute(f'''SELECT date, AVG(smap_value) FROM processed_data
+                                WHERE location = ? AND date BETWEEN ? AND ? AND {VALID_MOISTURE}
+                                GROUP BY date ORDER BY date DESC''',
+                            (location, start.isoformat(), end.isoformat())).fetchall()
+        baselines = _climatology(conn, location)
+    conn.close()
+
+    result = {
+        'location': location,
+        'date': day,
+        'units': UNITS[unit],
+        'meta': {
+            'thresholds': DROUGHT_THRESHOLDS,
+            'climatology_window_days': WINDOW_DAYS,
+            'climatology_years': None,
+            'climatology_computed_at': None
+        }
+    }
+    if not rows or rows[0][0] != day:
+        result.update({'status': 'no_data', 'value': None, 'percentile': None,
+                       'class': 'unclassified', 'days_in_class': 0})
+        return _respond(result)
+
+    def classify(row_date, value):
+        baseline = baselines.get(day_of_year(date.fromisoformat(row_date)))
+        percentile = percentile_of(value, baseline[0]) if baseline else None
+        return percentile, _drought_class(percentile)
+
+    percentile, current = classify(*rows[0])
+    # Consecutive observations in the current class; missing days neither break nor extend the run
+    days_in_class = 0
+    for row in rows:
+        if classify(*row)[1] != current:
+            break
+        days_in_class += 1
+
+    baseline = baselines.get(day_of_year(date.fromisoformat(day)))
+    if baseline:
+        result['meta'].update({'climatology_years': baseline[1], 'climatology_computed_at': baseline[2]})
+    result.update({
+        'status': 'ok',
+        'value': _format_moisture(rows[0][1], unit, precision),
+        'percentile': round(percentile, 1) if percentile is not None else None,
+        'class': current,
+        'days_in_class': days_in_class
+    })
+    return _respond(result)
+
+@app.route('/grid')
+def get_grid():
+    """Definition of the EASE-Grid 2.0 grid the product's cells are laid out on"""
+    grid = _grid_option()
+    x0, y0 = grid.origin
+    north = grid.bounds(0, 0)['north']
+    return _respond({
+        'name': grid.name,
+        'projection': 'EPSG:6933',
+        'product': PRODUCT,
+        'resolution_km': grid.resolution_km,
+        'cell_size_m': grid.cell_size,
+        'rows': grid.rows,
+        'cols': grid.cols,
+        'origin': {'x': x0, 'y': y0},
+        'latitude_extent': {'south': -north, 'north': north}
+    })
+
+def _cell_option(grid):
+    """(row, col) from the row/col parameters, or of the cell containing lat/lon; the forms are exclusive"""
+    query = request.query
+    by_index = query.get('row') is not None or query.get('col') is not None
+    if by_index and (query.get('lat') is not None or query.get('lon') is not None):
+        raise _bad_request("row/col can't be combined with lat/lon")
+    if by_index:
+        try:
+            return int(query.get('row')), int(query.get('col'))
+        except (TypeError, ValueError):
+            raise _bad_request("row and col must both be integers")
+    lat = _parse_coordinate('lat', query.get('lat'), 90)
+    lon = _parse_coordinate('lon', query.get('lon'), 180)
+    try:
+        return grid.cell(lat, lon)
+    except ValueError as e:
+        raise _bad_request(str(e))
+
+@app.route('/grid/cell')
+def get_grid_cell():
+    """A grid cell, by row/col or as the cell containing a point, with its center and corners"""
+    grid = _grid_option()
+    row, col = _cell_option(grid)
+    try:
+        center_lat, center_lon = grid.center(row, col)
+    except ValueError as e:
+        # Out-of-range indices; the message gives the grid's valid ranges
+        raise _bad_request(str(e))
+    return _respond({
+        'grid': grid.name,
+        'row': row,
+        'col': col,
+        'center': {'latitude': center_lat, 'longitude': center_lon},
+        'corners': [{'latitude': corner_lat, 'longitude': corner_lon}
+                    for corner_lat, corner_lon in grid.corners(row, col)],
+        'bounds': grid.bounds(row, col)
+    })
+
+@app.route('/et0', method='POST')
+def calculate_et0():
+    """FAO-56 reference evapotranspiration for one day or a batch of days"""
+    body = _json_body()
+    try:
+        results = _et0_results(body)
+    except InvalidInput as e:
+        raise _invalid(e)
+    return _respond({'units': 'mm/day', 'data': results} if 'days' in body else {'units': 'mm/day', **results[0]})
+
+def _et0_results(body):
+    """Validate an /et0 body and compute each day; errors carry the failing JSON Pointer"""
+    validation.expect(body, 'object', pointer())
+    batch = 'days' in body
+    if batch:
+        validation.expect(body['days'], 'array', pointer('days'))
+        if not body['days']:
+            raise InvalidInput("days must not be empty", pointer('days'), 'array', body['days'])
+        if len(body['days']) > MAX_ET0_DAYS:
+            raise InvalidInput(f"at most {MAX_ET0_DAYS} days per request", pointer('days'))
+        # Fields at the top level (typically latitude/elevation) apply to every day
+        shared = {key: value for key, value in body.items() if key != 'days'}
+        days = body['days']
+    else:
+        shared, days = {}, [body]
+
+    results = []
+    for i, day in enumerate(days):
+        at = pointer('days', i) if batch else pointer()
+        validation.expect(day, 'object', at)
+        merged = {**shared, **day}
+
+        def locate(field):
+            # A shared value is reported where the client wrote it, at the top level
+            return pointer('days', i, field) if batch and field in day else pointer(field)
+
+        unknown = sorted(set(merged) - set(ET0_FIELDS) - {'date'})
+        if unknown:
+            raise InvalidInput(f"unknown fields: {', '.join(unknown)}", locate(unknown[0]))
+        try:
+            day_date = date.fromisoformat(merged.get('date'))
+        except (TypeError, ValueError):
+            raise InvalidInput("date must be a date in YYYY-MM-DD format", locate('date'),
+                               'string (YYYY-MM-DD)', merged.get('date')) from None
+        for field in ET0_FIELDS:
+            if merged.get(field) is not None:
+                validation.expect(merged[field], 'number', locate(field))
+        try:
+            results.append(reference_et0(day_date, **{field: merged.get(field) for field in ET0_FIELDS}))
+        except WeatherError as e:
+            raise InvalidInput(str(e), locate(e.field) if e.field else at) from None
+    return results
 
 if __name__ == "__main__":
-    serve(app, host='0.0.0.0', port=8080)
\ No newline at end of file
+    logging.basicConfig(level=logging.INFO)
+    if not cursors.SECRET:
+        logging.warning("OPENFLOW_CURSOR_SECRET is not set; pagination cursors are checksummed but can be forged")
+    report = run_checks(api_db=DB_PATH)
+    if '--check' in sys.argv[1:]:
+        print(json.dumps(report, indent=2))
+        sys.exit(0 if all(item['ok'] for item in report) else 1)
+    # Failures are logged by run_checks; keep serving whatever data is already there
+    serve(app, host='0.0.0.0', port=8080)
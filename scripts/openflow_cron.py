@@ -4,6 +4,8 @@ import logging
 import sqlite3
 from datetime import datetime
 from downloadSMAP import main as downloadAndProcessSmap
+from anomaly import ingest
+from notify import configured, dispatch, notify
 
 # Set up logging
 LOG_PATH = os.getenv('OPENFLOW_LOG_PATH', '/var/log/openflow_cron.log')
@@ -43,18 +45,29 @@ def store_processed_data(processed_data):
     cursor = conn.cursor()
     cursor.execute('''CREATE TABLE IF NOT EXISTS processed_data 
                       (date TEXT, location TEXT, smap_value REAL, vegdri_value REAL)''')
-    cursor.executemany('''INSERT INTO processed_data (date, location, smap_value, vegdri_value)
-                          VALUES (?, ?, ?, ?)''', processed_data)
+    # Serves date filters and keyset pagination on the API
+    cursor.execute('''CREATE INDEX IF NOT EXISTS idx_processed_data_date_location
+                      ON processed_data (date, location)''')
     conn.commit()
+    run_id, status, reasons = ingest(conn, processed_data)
+    if reasons and configured():
+        # ingest queued the alert in the same transaction as the run; deliver it now,
+        # leaving any failure to the outbox dispatcher cron job
+        dispatch(conn)
     conn.close()
+    if reasons:
+        return
     print("Processed data stored in the database")
 
 async def main():
+    start_date, end_date = '2023-01-01', '2023-01-31'
     try:
-        downloadAndProcessSmap('2023-01-01', '2023-01-31')
+        downloadAndProcessSmap(start_date, end_date)
     except Exception as e:
         print(f"An error occurred: {str(e)}")
         logging.error(f"An error occurred: {str(e)}")
+        # The run has already failed; a notification problem is logged, never raised
+        notify('ingestion_failed', start_date=start_date, end_date=end_date, error=str(e), log_path=LOG_PATH)
 
 if __name__ == "__main__":
     asyncio.run(main())
\ No newline at end of file
@@ -0,0 +1,200 @@
+import os
+import sqlite3
+import logging
+import argparse
+import time
+from pathlib import Path
+from datetime import date, datetime, timedelta
+from typing import List, Dict, Optional
+
+import http_client
+from stations import Station
+
+"""
+Daily precipitation from the Open-Meteo archive API. PrecipitationProcessor fills
+precipitation_features for the training stations; the command line keeps the API
+database's precipitation table, keyed by processed_data location, up to date:
+
+    python3 openmeteo.py register LOCATION --lat 40.0 --lon -105.5
+    python3 openmeteo.py update [--days 14]     # every registered location (cron)
+    python3 openmeteo.py fetch LOCATION --lat 40.0 --lon -105.5 --start 2024-05-01 --end 2024-05-31
+
+fetch takes explicit coordinates, so a location can be filled ad hoc without registering it.
+"""
+
+logger = logging.getLogger(__name__)
+
+# The archive lags real time by a few days; recent days are re-fetched as they are revised
+DEFAULT_UPDATE_DAYS = 14
+
+
+def fetch_daily_precipitation(lat: float, lon: float, start_date: datetime,
+                              end_date: datetime) -> Optional[Dict[str, float]]:
+    """Get daily precipitation totals (mm) for any coordinate, keyed by YYYY-MM-DD"""
+    params = {
+        'latitude': round(float(lat), 4),
+        'longitude': round(float(lon), 4),
+        'start_date': start_date.strftime('%Y-%m-%d'),
+        'end_date': end_date.strftime('%Y-%m-%d'),
+        'daily': 'precipitation_sum',
+        'timezone': 'UTC'
+    }
+
+    try:
+        response = http_client.session().get(PrecipitationProcessor.BASE_URL, params=params)
+        if response.status_code == 429:
+            logger.error("Open-Meteo rate limit exceeded")
+            return None
+        response.raise_for_status()
+
+        daily = response.json().get('daily', {})
+        days = daily.get('time', [])
+        totals = daily.get('precipitation_sum', [])
+        return {day: total for day, total in zip(days, totals) if total is not None}
+
+    except Exception as e:
+        logger.error(f"Error fetching precipitation for ({lat}, {lon}): {e}")
+        return None
+
+
+class PrecipitationProcessor:
+    """Fetch daily precipitation for stations from the Open-Meteo archive API"""
+    BASE_URL = "https://archive-api.open-meteo.com/v1/archive"
+    REQUEST_DELAY = 1.0  # Seconds between requests, well under the free tier's per-minute limit
+
+    def __init__(self, stations: List[Station], db_path: Path):
+        self.stations = stations
+        self.db_path = db_path
+        logger.info(f"Initialized Precipitation Processor with {len(stations)} stations")
+
+    def process(self, start_date: datetime, end_date: datetime) -> int:
+        """Fetch and store precipitation for all stations, returning the number of stored days"""
+        stored = 0
+        for i, station in enumerate(self.stations):
+            if i > 0:
+                time.sleep(self.REQUEST_DELAY)
+
+            totals = fetch_daily_precipitation(station.latitude, station.longitude, start_date, end_date)
+            if not totals:
+                logger.warning(f"No precipitation data for station {station.id}")
+                continue
+
+            stored += self._save_station_data(station.id, totals)
+            logger.info(f"Stored {len(totals)} days of precipitation for {station.id}")
+
+        return stored
+
+    def _save_station_data(self, station_id: str, totals: Dict[str, float]) -> int:
+        """Save daily totals using the same midnight timestamps as smap_features"""
+        try:
+            with sqlite3.connect(self.db_path) as conn:
+                conn.executemany('''
+                    INSERT OR REPLACE INTO precipitation_features
+                    (timestamp, station_id, precipitation)
+                    VALUES (?, ?, ?)
+                ''', [
+                    (int(datetime.strptime(day, '%Y-%m-%d').timestamp()), station_id, total)
+                    for day, total in totals.items()
+                ])
+            return len(totals)
+
+        except Exception as e:
+            logger.error(f"Error saving precipitation for station {station_id}: {e}")
+            return 0
+
+
+def create_tables(conn: sqlite3.Connection):
+    """Create the API database's precipitation tables if they don't exist"""
+    conn.execute('''
+        CREATE TABLE IF NOT EXISTS precipitation (
+            date TEXT,
+            location TEXT,              -- Matches processed_data.location
+            precipitation REAL,         -- Daily total in mm
+            PRIMARY KEY (date, location)
+        )
+    ''')
+    conn.execute('''
+        CREATE TABLE IF NOT EXISTS precipitation_locations (
+            location TEXT PRIMARY KEY,
+            latitude REAL,
+            longitude REAL
+        )
+    ''')
+
+
+def register(conn: sqlite3.Connection, location: str, lat: float, lon: float):
+    """Add or move a location fetched by update"""
+    create_tables(conn)
+    with conn:
+        conn.execute('INSERT OR REPLACE INTO precipitation_locations (location, latitude, longitude) VALUES (?, ?, ?)',
+                     (location, lat, lon))
+
+
+def fetch_location(conn: sqlite3.Connection, location: str, lat: float, lon: float,
+                   start_date: datetime, end_date: datetime) -> int:
+    """Fetch and store one location's daily totals, returning the number of stored days"""
+    totals = fetch_daily_precipitation(lat, lon, start_date, end_date)
+    if not totals:
+        logger.warning(f"No precipitation data for {location}")
+        return 0
+    create_tables(conn)
+    with conn:
+        conn.executemany('INSERT OR REPLACE INTO precipitation (date, location, precipitation) VALUES (?, ?, ?)',
+                         [(day, location, total) for day, total in totals.items()])
+    return len(totals)
+
+
+def update(conn: sqlite3.Connection, days: int = DEFAULT_UPDATE_DAYS) -> int:
+    """Fetch the trailing days for every registered location, spacing requests for the rate limit"""
+    create_tables(conn)
+    end_date = datetime.combine(date.today(), datetime.min.time())
+    start_date = end_date - timedelta(days=days - 1)
+    stored = 0
+    locations = conn.execute('SELECT location, latitude, longitude FROM precipitation_locations').fetchall()
+    for i, (location, lat, lon) in enumerate(locations):
+        if i > 0:
+            time.sleep(PrecipitationProcessor.REQUEST_DELAY)
+        stored += fetch_location(conn, location, lat, lon, start_date, end_date)
+    logger.info(f"Stored {stored} days of precipitation for {len(locations)} locations")
+    return stored
+
+
+def main():
+    logging.basicConfig(level=logging.INFO)
+    parser = argparse.ArgumentParser(description="Fetch daily precipitation from Open-Meteo into the API database")
+    parser.add_argument('--db', default=os.getenv('OPENFLOW_DB_PATH', '/var/lib/openflow/data.db'),
+                        help="API database path (default OPENFLOW_DB_PATH)")
+    actions = parser.add_subparsers(dest='action', required=True)
+    for name in ('register', 'fetch'):
+        action = actions.add_parser(name)
+        action.add_argument('location', help="processed_data location")
+        action.add_argument('--lat', type=float, required=True)
+        action.add_argument('--lon', type=float, required=True)
+    fetch = actions.choices['fetch']
+    fetch.add_argument('--start', type=date.fromisoformat, required=True, help="YYYY-MM-DD")
+    fetch.add_argument('--end', type=date.fromisoformat, required=True, help="YYYY-MM-DD")
+    actions.add_parser('update').add_argument('--days', type=int, default=DEFAULT_UPDATE_DAYS,
+                                              help="Trailing days to fetch")
+    args = parser.parse_args()
+    if args.action in ('register', 'fetch') and not (-90 <= args.lat <= 90 and -180 <= args.lon <= 180):
+        parser.error("--lat must be within -90..90 and --lon within -180..180")
+
+    conn = sqlite3.connect(args.db)
+    try:
+        if args.action == 'register':
+            register(conn, args.location, args.lat, args.lon)
+        elif args.action == 'fetch':
+            if args.end < args.start:
+                parser.error("--end must not be before --start")
+            count = fetch_location(conn, args.location, args.lat, args.lon,
+                                   datetime.combine(args.start, datetime.min.time()),
+                                   datetime.combine(args.end, datetime.min.time()))
+            logger.info(f"Stored {count} days of precipitation for {args.location}")
+        else:
+            update(conn, args.days)
+    finally:
+        conn.close()
+
+
+if __name__ == "__main__":
+    main()
@@ -0,0 +1,129 @@
+import os
+import sqlite3
+import logging
+import argparse
+import json
+import tempfile
+from pathlib import Path
+from typing import Callable, Dict, List, Optional
+
+import http_client
+import tempdirs
+
+"""
+Deployment self-check: verifies the things a 2am ingestion run depends on and reports
+each as pass/fail. Run directly, or via `openflow_api.py --check`, in deploy pipelines;
+the exit code is nonzero when any check fails.
+"""
+
+logger = logging.getLogger(__name__)
+
+ENDPOINTS = {
+    'cmr': 'https://cmr.earthdata.nasa.gov/search/',
+    'open_meteo': 'https://archive-api.open-meteo.com/v1/archive'
+}
+API_COLUMNS = ['date', 'location', 'smap_value', 'vegdri_value']
+
+
+def check_api_schema(db_path: str) -> str:
+    conn = sqlite3.connect(f'file:{db_path}?mode=ro', uri=True)
+    try:
+        columns = [row[1] for row in conn.execute("PRAGMA table_info(processed_data)")]
+    finally:
+        conn.close()
+    if not columns:
+        raise RuntimeError(f"processed_data table missing in {db_path}")
+    missing = set(API_COLUMNS) - set(columns)
+    if missing:
+        raise RuntimeError(f"processed_data is missing columns: {', '.join(sorted(missing))}")
+    return f"processed_data has {', '.join(columns)}"
+
+
+def check_pipeline_schema(db_path: str) -> str:
+    from init_dbs import check_database_structure
+    conn = sqlite3.connect(f'file:{db_path}?mode=ro', uri=True)
+    try:
+        if not check_database_structure(conn):
+            raise RuntimeError(f"feature tables in {db_path} don't match the expected schema")
+    finally:
+        conn.close()
+    return "feature tables match"
+
+
+def check_hdf5() -> str:
+    import h5py
+    # An in-memory file exercises the library without touching disk
+    with h5py.File('selfcheck.h5', 'w', driver='core', backing_store=False) as f:
+        f['probe'] = [1, 2, 3]
+        if list(f['probe'][:]) != [1, 2, 3]:
+            raise RuntimeError("HDF5 round trip returned different data")
+    return f"h5py {h5py.__version__}, HDF5 {h5py.version.hdf5_version}"
+
+
+def check_endpoint(url: str) -> str:
+    response = http_client.session().head(url, allow_redirects=True)
+    # Anything short of a server error means DNS, TLS and routing work
+    if response.status_code >= 500:
+        raise RuntimeError(f"HTTP {response.status_code}")
+    return f"HTTP {response.status_code}"
+
+
+def check_earthdata() -> str:
+    if not (os.getenv('EARTHDATA_USERNAME') and os.getenv('EARTHDATA_PASSWORD')):
+        raise RuntimeError("EARTHDATA_USERNAME and EARTHDATA_PASSWORD are not set")
+    import earthaccess
+    auth = earthaccess.login(strategy="environment")
+    if not getattr(auth, 'authenticated', False):
+        raise RuntimeError("Earthdata login was rejected")
+    return "authenticated"
+
+
+def check_temp_dir(root: Path) -> str:
+    root.mkdir(parents=True, exist_ok=True)
+    with tempfile.NamedTemporaryFile(dir=root, prefix=tempdirs.PREFIX + 'selfcheck-'):
+        pass
+    tempdirs.ensure_free_space(root)
+    return f"{root} writable with at least {tempdirs.MIN_FREE_BYTES} bytes free"
+
+
+def run_checks(api_db: Optional[str] = None, pipeline_db: Optional[str] = None,
+               network: bool = True) -> List[Dict]:
+    """Run every applicable check, returning one {'check', 'ok', 'detail'} dict each"""
+    checks: Dict[str, Callable[[], str]] = {}
+    if api_db:
+        checks['api_schema'] = lambda: check_api_schema(api_db)
+    if pipeline_db:
+        checks['pipeline_schema'] = lambda: check_pipeline_schema(pipeline_db)
+    checks['hdf5'] = check_hdf5
+    checks['temp_dir'] = lambda: check_temp_dir(tempdirs.TEMP_ROOT)
+    if network:
+        for name, url in ENDPOINTS.items():
+            checks[f'endpoint_{name}'] = lambda url=url: check_endpoint(url)
+        checks['earthdata_login'] = check_earthdata
+
+    report = []
+    for name, check in checks.items():
+        try:
+            report.append({'check': name, 'ok': True, 'detail': check()})
+        except Exception as e:
+            report.append({'check': name, 'ok': False, 'detail': f"{type(e).__name__}: {e}"})
+            logger.error(f"Self-check {name} failed: {e}")
+    return report
+
+
+def main(argv=None):
+    logging.basicConfig(level=logging.INFO)
+    parser = argparse.ArgumentParser(description="Verify an OpenFlow deployment")
+    parser.add_argument('--api-db', default=os.getenv('OPENFLOW_DB_PATH'), help="API database to check")
+    parser.add_argument('--pipeline-db', default=os.getenv('OPENFLOW_SQL_PATH'),
+                        help="Feature database written by setup_ea_datasets")
+    parser.add_argument('--offline', action='store_true', help="Skip endpoint and login checks")
+    args = parser.parse_args(argv)
+
+    report = run_checks(args.api_db, args.pipeline_db, network=not args.offline)
+    print(json.dumps(report, indent=2))
+    raise SystemExit(0 if all(item['ok'] for item in report) else 1)
+
+
+if __name__ == "__main__":
+    main()
@@ -1,15 +1,18 @@
 import earthaccess
 import logging
+import json
 from datetime import datetime
 from typing import Dict, Optional, Tuple, List
 from pathlib import Path
-import requests
+import granules
+import http_client
 from stations import Station, get_usgs_coordinates, get_dwr_coordinates
 import os
 
 #from smapprocessor import SMAPProcessor
 from soilgrids import SoilGridsProcessor
 from staticprocessor import StaticProcessor
+from openmeteo import PrecipitationProcessor
 from init_dbs import setup_database, store_stations
 
 """
@@ -67,7 +70,7 @@ class DatasetAnalyzer:
     def load_site_ids(self) -> List[Tuple[str, str]]:
         """Load site IDs from URL"""
         try:
-            response = requests.get(self.SITE_IDS_URL)
+            response = http_client.session().get(self.SITE_IDS_URL)
             response.raise_for_status()
             site_ids = response.text.strip().split()
             return [tuple(site_id.split(':')) for site_id in site_ids]
@@ -165,6 +168,10 @@ class DatasetAnalyzer:
         return stations
 
 def main():
+    # Fail fast on bad proxy, CA or granule source settings before any download starts
+    http_client.configure()
+    granules.parse_sources(granules.SOURCES)
+
     # Set up database path
     if (os.getenv('OPENFLOW_SQL_PATH')):
         db_path = os.getenv('OPENFLOW_SQL_PATH')
@@ -177,6 +184,14 @@ def main():
     #analyzer.print_coverage_summary()
     common_start, common_end = analyzer.find_common_period()
 
+    if os.getenv('OPENFLOW_SMAP_DRY_RUN'):
+        # Preview an ingestion: no schema setup, station rows, or feature writes
+        from smapprocessor import SMAPProcessor
+        processor = SMAPProcessor(analyzer.stations, common_start, common_end, dry_run=True,
+                                  synthetic=bool(os.getenv('OPENFLOW_SMAP_SYNTHETIC')),
+                                  seed=int(os.getenv('OPENFLOW_SMAP_SEED', '0')))
+        print(json.dumps(processor.dry_run_report, indent=2))
+        return
 
     setup_database(db_path)
     store_stations(analyzer.stations, db_path)
@@ -184,6 +199,11 @@ def main():
     # Process SMAP dataset
     #SMAPProcessor(analyzer.stations, common_start, common_end)
     #SMAPProcessor.readout(db_path)
+    if os.getenv('OPENFLOW_SMAP_SYNTHETIC'):
+        # Offline development and CI: fake data, flagged synthetic=1 in smap_features
+        from smapprocessor import SMAPProcessor
+        SMAPProcessor(analyzer.stations, common_start, common_end, synthetic=True,
+                      seed=int(os.getenv('OPENFLOW_SMAP_SEED', '0')))
 
     # Process Static datasets
     #StaticProcessor(analyzer.stations)
@@ -194,5 +214,11 @@ def main():
     # Show results
     processor.readout(soil_data)
 
+    # Precipitation is supplementary; a failure here must not stop the rest of the pipeline
+    try:
+        PrecipitationProcessor(analyzer.stations, db_path).process(common_start, common_end)
+    except Exception as e:
+        logger.error(f"Precipitation processing failed: {e}")
+
 if __name__ == "__main__":
     main()
\ No newline at end of file
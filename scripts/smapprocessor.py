@@ -1,17 +1,39 @@
 import sqlite3
 import time
+import zlib
 import numpy as np
 import logging
 from pathlib import Path
 from datetime import datetime, timedelta
 from typing import List, Optional, Tuple, Dict
-import h5py
+from urllib.parse import urlparse
 import earthaccess
 
 from stations import Station
+from flags import FILL_VALUE as SURFACE_FLAG_FILL, exclusion_mask
+from grid import GRIDS, PRODUCT_GRIDS
+import granules
+import smaptime
+import tempdirs
 
 logger = logging.getLogger(__name__)
 
+# A missing HDF5 shared library surfaces as an OSError from the dlopen, not an ImportError
+try:
+    import h5py
+    HDF5_ERROR = None
+except (ImportError, OSError) as e:
+    h5py = None
+    HDF5_ERROR = str(e)
+    logger.warning(f"HDF5 unavailable, granule ingestion is disabled (synthetic mode still works): {e}")
+
+
+PRODUCT_VERSION = "006"
+
+
+class Hdf5Unavailable(RuntimeError):
+    """Granule processing was requested where h5py or the HDF5 library can't be loaded"""
+
 class SMAPProcessor:
     """Processor for SMAP soil moisture data"""
     
@@ -21,7 +43,13 @@ class SMAPProcessor:
                 chunk_size: int = 50, # Number of pixels to process at once
                 vegetation_threshold: float = 5.0,
                 watershed_file: Optional[Path] = None,
-                dem_file: Optional[Path] = None):
+                dem_file: Optional[Path] = None,
+                synthetic: bool = False,
+                seed: int = 0,
+                temp_dir: Optional[Path] = None,
+                exclude_surface: Tuple[str, ...] = (),
+                dry_run: bool = False,
+                granule_sources: Optional[str] = None):
         """
         Initialize SMAP processor with enhanced hydrological parameters
         
@@ -35,6 +63,12 @@ class SMAPProcessor:
             watershed_file: Optional GIS file with watershed boundaries
             dem_file: Optional Digital Elevation Model file
             chunk_size: Number of pixels to process at once
+            synthetic: Generate deterministic fake data instead of downloading granules
+            seed: Random seed for synthetic data
+            temp_dir: Root for download scratch space (default OPENFLOW_TEMP_DIR or temp_smap)
+            exclude_surface: surface_flag groups (see flags.EXCLUDE_GROUPS) whose pixels are skipped
+            dry_run: Download and process as usual but write nothing; results go to dry_run_report
+            granule_sources: Ordered download sources (default OPENFLOW_GRANULE_SOURCES, see granules.py)
 
         """
         self.stations = stations
@@ -44,6 +78,16 @@ class SMAPProcessor:
         self.chunk_size = chunk_size
         self.frozen_soil_threshold = frozen_soil_threshold
         self.vegetation_threshold = vegetation_threshold
+        self.synthetic = synthetic
+        self.seed = seed
+        self.temp_root = temp_dir if temp_dir is not None else tempdirs.TEMP_ROOT
+        self.surface_mask = exclusion_mask(exclude_surface)
+        self.dry_run = dry_run
+        # Validated up front so a bad template fails before any login or download
+        self.granule_sources = granules.parse_sources(granule_sources or granules.SOURCES)
+        self.granule_sources_used: Dict[str, str] = {}
+        self.dry_run_report = {'days': 0, 'rows': 0, 'replacements': 0, 'total_pixels': 0,
+                               'fill_pixels': 0, 'out_of_range_pixels': 0} if dry_run else None
         
         # Load watershed boundaries if provided
         self.watersheds = None
@@ -67,13 +111,19 @@ class SMAPProcessor:
             except Exception as e:
                 logger.error(f"Failed to load DEM: {e}")
         
+        if not synthetic and h5py is None:
+            raise Hdf5Unavailable(f"Reading SMAP granules needs h5py and the HDF5 library: {HDF5_ERROR}")
+
         # Initialize auth but don't store it
-        try:
-            earthaccess.login(strategy="environment")
-            logger.info("Authentication successful")
-        except Exception as e:
-            logger.error(f"Authentication error: {e}")
-            raise
+        if synthetic:
+            logger.warning("SYNTHETIC MODE: generating fake SMAP data, nothing will be downloaded")
+        else:
+            try:
+                earthaccess.login(strategy="environment")
+                logger.info("Authentication successful")
+            except Exception as e:
+                logger.error(f"Authentication error: {e}")
+                raise
         
         logger.info(f"Initialized SMAP Processor with:")
         logger.info(f"- {len(stations)} stations")
@@ -87,32 +137,41 @@ class SMAPProcessor:
 
     def process_data(self):
         """Process SMAP data day by day, combining AM and PM granules"""
-        temp_dir = Path("temp_smap")
-        temp_dir.mkdir(exist_ok=True)
+        if self.synthetic:
+            self._process_synthetic_data()
+            self._log_dry_run()
+            return
+
+        started = time.time()
+        self.temp_root.mkdir(parents=True, exist_ok=True)
+        tempdirs.sweep(self.temp_root, started)
+        temp_dir = tempdirs.job_dir(self.temp_root, 'smap', started)
         
         try:
             current_date = self.start_date
             while current_date <= self.end_date:
                 next_date = current_date + timedelta(days=1)
                 logger.info(f"Processing date: {current_date.date()}")
+                # Abort the run rather than filling the disk mid-download
+                tempdirs.ensure_free_space(temp_dir)
                 
                 try:
                     # Get both AM and PM granules for the day
-                    granules = earthaccess.search_data(
+                    results = earthaccess.search_data(
                         short_name="SPL3SMP_E",
-                        version="006",
+                        version=PRODUCT_VERSION,
                         provider="NSIDC_ECS",
                         temporal=(current_date, next_date),
                         count=2
                     )
                     
-                    if not granules:
+                    if not results:
                         logger.warning(f"No granules found for {current_date.date()}")
                         current_date = next_date
                         continue
                     
                     # Process and combine AM/PM data
-                    daily_data = self._process_daily_granules(granules, temp_dir, current_date)
+                    daily_data = self._process_daily_granules(results, temp_dir, current_date)
                     
                     if daily_data:
                         self._save_daily_data(daily_data)
@@ -135,41 +194,98 @@ class SMAPProcessor:
                 temp_dir.rmdir()
             except Exception as e:
                 logger.error(f"Error cleaning temp directory: {e}")
+            tempdirs.sweep(self.temp_root, started)
+            logger.info(f"Temp directory {self.temp_root} holds {tempdirs.usage_bytes(self.temp_root)} bytes")
+            self._log_dry_run()
+
+    def _log_dry_run(self):
+        if self.dry_run:
+            logger.info(f"DRY RUN: nothing was written. Summary: {self.dry_run_report}")
+
+    def _process_synthetic_data(self):
+        """Generate and save fake AM/PM data through the same combine and save path as real granules"""
+        current_date = self.start_date
+        while current_date <= self.end_date:
+            timestamp = int(current_date.timestamp())
+            daily_data = {}
+            for station in self.stations:
+                am_data, pm_data = self._synthetic_passes(station, current_date)
+                combined = self._combine_am_pm_data(timestamp, station.id, am_data, pm_data)
+                if combined:
+                    daily_data[station.id] = combined
+
+            if daily_data:
+                self._save_daily_data(daily_data)
+                logger.warning(f"SYNTHETIC MODE: saved fake data for {current_date.date()}")
+            current_date += timedelta(days=1)
+
+    def _synthetic_passes(self, station: Station, date: datetime) -> Tuple[Dict, Dict]:
+        """Plausible AM/PM soil moisture: seasonal sinusoid plus noise, deterministic per seed/station/day"""
+        rng = np.random.default_rng([self.seed, zlib.crc32(station.id.encode()), date.toordinal()])
+        day_of_year = date.timetuple().tm_yday
+        # Wettest in early spring, driest in early autumn
+        seasonal = 0.25 + 0.08 * np.cos(2 * np.pi * (day_of_year - 80) / 365)
+        am = float(np.clip(seasonal + rng.normal(0, 0.02), 0.02, 0.5))
+        pm = float(np.clip(am + rng.normal(0, 0.01), 0.02, 0.5))
+        return (
+            {'soil_moisture': am, 'quality_flag': 0, 'surface_flag': 0, 'is_am': True,
+             'observation_time': smaptime.rfc3339(smaptime.nominal_overpass(date, station.longitude, True))},
+            {'soil_moisture': pm, 'quality_flag': 0, 'surface_flag': 0, 'is_am': False,
+             'observation_time': smaptime.rfc3339(smaptime.nominal_overpass(date, station.longitude, False))}
+        )
 
     def _save_daily_data(self, daily_data: Dict[str, Dict]):
         """Save daily data to database"""
+        if self.dry_run:
+            self._record_dry_run(daily_data)
+            return
         try:
             with sqlite3.connect("data/earth_data.db") as conn:
                 for data in daily_data.values():
                     conn.execute('''
                         INSERT OR REPLACE INTO smap_features 
-                        (timestamp, station_id, soil_moisture, quality_flag)
-                        VALUES (:timestamp, :station_id, :soil_moisture, :quality_flag)
-                    ''', data)
+                        (timestamp, station_id, soil_moisture, quality_flag, surface_flag, observation_time, synthetic)
+                        VALUES (:timestamp, :station_id, :soil_moisture, :quality_flag, :surface_flag,
+                                :observation_time, :synthetic)
+                    ''', dict(data, synthetic=int(self.synthetic)))
                 
                 logger.info(f"Saved {len(daily_data)} records to database")
                 
         except Exception as e:
             logger.error(f"Error saving daily data: {e}")
 
-    def _process_daily_granules(self, granules: List, temp_dir: Path, 
+    def _record_dry_run(self, daily_data: Dict[str, Dict]):
+        """Tally what _save_daily_data would write, opening the database read-only"""
+        report = self.dry_run_report
+        report['days'] += 1
+        report['rows'] += len(daily_data)
+        try:
+            with sqlite3.connect("file:data/earth_data.db?mode=ro", uri=True) as conn:
+                for data in daily_data.values():
+                    if conn.execute('SELECT 1 FROM smap_features WHERE timestamp = ? AND station_id = ?',
+                                    (data['timestamp'], data['station_id'])).fetchone():
+                        report['replacements'] += 1
+        except sqlite3.OperationalError as e:
+            # No database or table yet means nothing would be replaced
+            logger.info(f"DRY RUN: couldn't check for existing rows: {e}")
+
+    def _process_daily_granules(self, daily_granules: List, temp_dir: Path, 
                             date: datetime) -> Dict[str, Dict]:
         """Process AM and PM granules for a single day and combine the data"""
         daily_data = {}
         am_data = None
         pm_data = None
         
-        logger.info(f"Processing {len(granules)} granules for {date.date()}")
+        logger.info(f"Processing {len(daily_granules)} granules for {date.date()}")
         
-        for granule in granules:
+        for granule in daily_granules:
             try:
                 # Examine granule filename from downloaded file
-                downloaded = earthaccess.download(granule, local_path=str(temp_dir))
-                if not downloaded:
-                    logger.warning("Failed to download granule")
+                file_path = self._download(granule, temp_dir, date)
+                if not file_path:
+                    logger.warning("Failed to download granule from any source")
                     continue
-                    
-                file_path = downloaded[0]
+
                 file_name = Path(file_path).name
                 is_am = '_AM_' in file_name or '_A_' in file_name
                 is_pm = '_PM_' in file_name or '_P_' in file_name
@@ -223,6 +339,29 @@ class SMAPProcessor:
             
         return daily_data
 
+    def _download(self, granule, temp_dir: Path, date: datetime) -> Optional[str]:
+        """Fetch a granule from the first source that serves it, recording which one did"""
+        links = granule.data_links()
+        name = Path(urlparse(links[0]).path).name if links else None
+        for source in self.granule_sources:
+            try:
+                if source == granules.EARTHDATA:
+                    downloaded = earthaccess.download(granule, local_path=str(temp_dir))
+                    if not downloaded:
+                        raise RuntimeError("earthaccess downloaded nothing")
+                    file_path = str(downloaded[0])
+                elif name:
+                    file_path = str(granules.fetch(granules.expand(source, date, PRODUCT_VERSION, name), temp_dir))
+                else:
+                    continue
+            except Exception as e:
+                logger.warning(f"Granule source {source} failed: {e}")
+                continue
+            self.granule_sources_used[Path(file_path).name] = source
+            logger.info(f"Downloaded {Path(file_path).name} from {source}")
+            return file_path
+        return None
+
     def _process_granule(self, file_path: str, is_am: bool) -> Dict[str, Dict]:
         """Process a single SMAP granule file"""
         data = {}
@@ -338,12 +477,21 @@ class SMAPProcessor:
                 if am_data['quality_flag'] < pm_data['quality_flag']:
                     soil_moisture = am_data['soil_moisture']
                     quality_flag = am_data['quality_flag']
+                    surface_flag = am_data.get('surface_flag')
+                    observation_time = am_data.get('observation_time')
                 elif pm_data['quality_flag'] < am_data['quality_flag']:
                     soil_moisture = pm_data['soil_moisture']
                     quality_flag = pm_data['quality_flag']
+                    surface_flag = pm_data.get('surface_flag')
+                    observation_time = pm_data.get('observation_time')
                 else:
                     soil_moisture = (am_data['soil_moisture'] + pm_data['soil_moisture']) / 2
                     quality_flag = am_data['quality_flag']  # Same as PM flag
+                    # Conditions seen in either overpass apply to the average
+                    flags = [d['surface_flag'] for d in (am_data, pm_data) if d.get('surface_flag') is not None]
+                    surface_flag = flags[0] | flags[-1] if flags else None
+                    # An average has no single overpass time; record the first one it includes
+                    observation_time = am_data.get('observation_time') or pm_data.get('observation_time')
             else:
                 # Use whichever is available
                 data = am_data if am_data else pm_data
@@ -351,12 +499,16 @@ class SMAPProcessor:
                     return None
                 soil_moisture = data['soil_moisture']
                 quality_flag = data['quality_flag']
+                surface_flag = data.get('surface_flag')
+                observation_time = data.get('observation_time')
             
             return {
                 'timestamp': timestamp,
                 'station_id': station_id,
                 'soil_moisture': float(soil_moisture),
-                'quality_flag': int(quality_flag)
+                'quality_flag': int(quality_flag),
+                'surface_flag': int(surface_flag) if surface_flag is not None else None,
+                'observation_time': observation_time
             }
             
         except Exception as e:
@@ -550,6 +702,26 @@ class SMAPProcessor:
                         return data
                         
                     datasets[key] = f[path][:]
+
+                if self.dry_run:
+                    sm = datasets['soil_moisture']
+                    attrs = f[paths['soil_moisture']].attrs
+                    valid = sm != -9999.0
+                    self.dry_run_report['total_pixels'] += int(sm.size)
+                    self.dry_run_report['fill_pixels'] += int(np.sum(~valid))
+                    self.dry_run_report['out_of_range_pixels'] += int(np.sum(
+                        valid & ((sm < attrs.get('valid_min', 0.0)) | (sm > attrs.get('valid_max', 1.0)))))
+
+                # Surface flags are optional; without them nothing is masked or recorded
+                surface_path = f"{base_path}/{'surface_flag' if is_am else 'surface_flag_pm'}"
+                surface = f[surface_path][:] if surface_path in f else None
+                # Per-cell overpass times, seconds since J2000; also optional
+                time_path = f"{base_path}/{'tb_time_seconds' if is_am else 'tb_time_seconds_pm'}"
+                times = f[time_path][:] if time_path in f else None
+                if surface is not None and self.surface_mask:
+                    flagged = (surface != SURFACE_FLAG_FILL) & ((surface & int(self.surface_mask)) != 0)
+                    datasets['soil_moisture'] = np.where(flagged, -9999.0, datasets['soil_moisture'])
+                    logger.info(f"Excluded {np.sum(flagged)} pixels by surface flags")
                 
                 # Validate data ranges
                 sm_valid = np.sum(datasets['soil_moisture'] != -9999.0)
@@ -559,14 +731,20 @@ class SMAPProcessor:
                     f"({sm_valid/total_pixels*100:.1f}%)"
                 )
                 
+                grid = GRIDS[PRODUCT_GRIDS['SPL3SMP_E']]
+                on_grid = datasets['soil_moisture'].shape == (grid.rows, grid.cols)
+
                 # Process each station with more validation
                 for station in self.stations:
                     try:
+                        # Only search the cells around the station rather than the whole globe
+                        window = (grid.window(station.latitude, station.longitude, self.radius_km)
+                                  if on_grid else (slice(None), slice(None)))
                         result = self._get_station_data(
-                            datasets['soil_moisture'],
-                            datasets['retrieval_qual_flag'],
-                            datasets['latitude'],
-                            datasets['longitude'],
+                            datasets['soil_moisture'][window],
+                            datasets['retrieval_qual_flag'][window],
+                            datasets['latitude'][window],
+                            datasets['longitude'][window],
                             station.latitude,
                             station.longitude
                         )
@@ -577,6 +755,10 @@ class SMAPProcessor:
                                 data[station.id] = {
                                     'soil_moisture': float(sm_value),
                                     'quality_flag': quality_flag,
+                                    'surface_flag': self._station_surface_flag(surface, grid, station)
+                                                    if on_grid else None,
+                                    'observation_time': self._station_observation_time(times, grid, station)
+                                                        if on_grid else None,
                                     'is_am': is_am
                                 }
                                 logger.info(
@@ -593,6 +775,19 @@ class SMAPProcessor:
         return data
 
 
+    def _station_surface_flag(self, surface: Optional[np.ndarray], grid, station: Station) -> Optional[int]:
+        """surface_flag of the grid cell containing the station, None when unavailable"""
+        if surface is None:
+            return None
+        value = int(surface[grid.cell(station.latitude, station.longitude)])
+        return None if value == SURFACE_FLAG_FILL else value
+
+    def _station_observation_time(self, times: Optional[np.ndarray], grid, station: Station) -> Optional[str]:
+        """RFC 3339 overpass time of the station's grid cell, None when unavailable"""
+        if times is None:
+            return None
+        return smaptime.rfc3339(smaptime.from_j2000(float(times[grid.cell(station.latitude, station.longitude)])))
+
     def _get_station_data_chunked(self, sm, quality, lat, lon, target_lat, target_lon):
         """Process station data in chunks with stable distance calculation"""
         n_points = sm.size
@@ -0,0 +1,39 @@
+from datetime import datetime, timedelta, timezone
+from typing import Optional
+
+"""
+SMAP time conventions. Granule times such as tb_time_seconds count seconds since the
+J2000 epoch, 2000-01-01 11:58:55.816 UTC (12:00 TT), ignoring leap seconds.
+"""
+
+J2000_EPOCH = datetime(2000, 1, 1, 11, 58, 55, 816000, tzinfo=timezone.utc)
+FILL_VALUE = -9999.0
+# Nominal local solar times of the descending (AM) and ascending (PM) overpasses
+AM_OVERPASS_HOUR = 6
+PM_OVERPASS_HOUR = 18
+# Nominal revisit of each product: SMAP's swath covers the globe every 2-3 days, so a
+# given cell's daily composite has a retrieval on about one day in three
+PRODUCT_REVISIT_DAYS = {'SPL3SMP': 3, 'SPL3SMP_E': 3}
+
+
+def from_j2000(seconds: Optional[float]) -> Optional[datetime]:
+    """UTC datetime for a J2000 seconds value, None for fill or missing values"""
+    if seconds is None or seconds != seconds or seconds <= 0:
+        # Covers NaN, FILL_VALUE and any other negative sentinel
+        return None
+    return J2000_EPOCH + timedelta(seconds=float(seconds))
+
+
+def rfc3339(moment: Optional[datetime]) -> Optional[str]:
+    """Format a UTC datetime as RFC 3339 with a Z suffix, to whole seconds"""
+    if moment is None:
+        return None
+    return moment.astimezone(timezone.utc).replace(microsecond=0).strftime('%Y-%m-%dT%H:%M:%SZ')
+
+
+def nominal_overpass(day: datetime, longitude: float, is_am: bool) -> datetime:
+    """UTC time of the nominal 6am/6pm local solar overpass at a longitude on a date"""
+    hour = AM_OVERPASS_HOUR if is_am else PM_OVERPASS_HOUR
+    midnight = datetime(day.year, day.month, day.day, tzinfo=timezone.utc)
+    # Local solar time runs ahead of UTC by 4 minutes per degree east
+    return midnight + timedelta(hours=hour - longitude / 15)
@@ -2,11 +2,11 @@ import logging
 from pathlib import Path
 from typing import List, Dict, Optional, Tuple
 from owslib.wcs import WebCoverageService
-import requests
 import numpy as np
 from dataclasses import dataclass
 import time
 
+import http_client
 from stations import Station
 
 logger = logging.getLogger(__name__)
@@ -80,7 +80,7 @@ class SoilGridsProcessor:
                 'value': ['mean']
             }
             
-            response = requests.get(self.BASE_URL, params=params, timeout=30)
+            response = http_client.session().get(self.BASE_URL, params=params)
             
             if response.status_code == 200:
                 data = response.json()
@@ -2,6 +2,8 @@ import logging
 from dataclasses import dataclass
 from typing import Dict, Optional
 
+import http_client
+
 logger = logging.getLogger(__name__)
 
 @dataclass
@@ -28,7 +30,7 @@ def get_usgs_coordinates(site_number: str) -> Optional[Dict]:
     full_url = requests.Request('GET', base_url, params=params).prepare().url
     logger.info(f"Fetching USGS coordinates - URL: {full_url}")
     
-    response = requests.get(base_url, params=params)
+    response = http_client.session().get(base_url, params=params)
     lines = response.text.splitlines()
     
     try:
@@ -65,12 +67,11 @@ def get_dwr_coordinates(abbrev: str) -> Optional[Dict]:
         "abbrev": abbrev,
     }
     
-    import requests
     full_url = f"{base_url}?{'&'.join(f'{k}={v}' for k, v in params.items())}"
     logger.info(f"Fetching DWR coordinates - URL: {full_url}")
     
     try:
-        response = requests.get(base_url, params=params)
+        response = http_client.session().get(base_url, params=params)
         response.raise_for_status()
         data = response.json()
         
@@ -0,0 +1,83 @@
+import os
+import shutil
+import logging
+import time
+from pathlib import Path
+
+"""
+Scratch space for granule downloads. Each job works in its own prefixed subdirectory
+of the temp root so a killed run leaves something recognizable to sweep up later.
+"""
+
+logger = logging.getLogger(__name__)
+
+TEMP_ROOT = Path(os.getenv('OPENFLOW_TEMP_DIR', 'temp_smap'))
+PREFIX = 'openflow-'
+STALE_AFTER = float(os.getenv('OPENFLOW_TEMP_STALE_HOURS', '24')) * 3600
+# An SPL3SMP_E granule is roughly 300 MB and a day needs AM and PM
+MIN_FREE_BYTES = int(os.getenv('OPENFLOW_TEMP_MIN_FREE_MB', '700')) * 1024 * 1024
+
+
+def job_dir(root: Path, name: str, started: float) -> Path:
+    """Create the working directory for one job under root"""
+    path = root / f"{PREFIX}{name}-{os.getpid()}-{int(started)}"
+    path.mkdir(parents=True, exist_ok=True)
+    return path
+
+
+def ensure_free_space(path: Path, required: int = MIN_FREE_BYTES):
+    """Raise OSError when the filesystem holding path has less than required bytes free"""
+    available = shutil.disk_usage(path).free
+    if available < required:
+        raise OSError(f"Not enough free space in {path}: {required} bytes required, {available} available")
+
+
+def _newest_mtime(path: Path) -> float:
+    """Latest modification time of path or anything inside it"""
+    newest = path.lstat().st_mtime
+    if path.is_dir() and not path.is_symlink():
+        for child in path.rglob('*'):
+            try:
+                newest = max(newest, child.lstat().st_mtime)
+            except OSError:
+                pass
+    return newest
+
+
+def sweep(root: Path, job_started: float, stale_after: float = STALE_AFTER) -> int:
+    """
+    Remove orphaned job directories left in root by killed runs
+
+    Only direct children of root carrying PREFIX are considered, and only when nothing in
+    them was modified within stale_after seconds before job_started, so the running job
+    and anything outside root are never touched. Returns the number of entries removed.
+    """
+    if not root.is_dir():
+        return 0
+
+    cutoff = min(job_started, time.time()) - stale_after
+    removed = 0
+    for entry in root.iterdir():
+        if not entry.name.startswith(PREFIX):
+            continue
+        try:
+            if _newest_mtime(entry) >= cutoff:
+                continue
+            if entry.is_dir() and not entry.is_symlink():
+                shutil.rmtree(entry)
+            else:
+                entry.unlink()
+            removed += 1
+        except OSError as e:
+            logger.error(f"Error removing stale temp entry {entry}: {e}")
+
+    if removed:
+        logger.info(f"Removed {removed} stale temp entries from {root}")
+    return removed
+
+
+def usage_bytes(root: Path) -> int:
+    """Total size of regular files under root"""
+    if not root.is_dir():
+        return 0
+    return sum(f.lstat().st_size for f in root.rglob('*') if f.is_file() and not f.is_symlink())
@@ -0,0 +1,122 @@
+import math
+import unittest
+import sys
+import os
+from datetime import date
+
+# Add the parent directory to the Python path
+sys.path.append(os.path.dirname(os.path.dirname(os.path.abspath(__file__))))
+
+from agro import (WeatherError, daylight_hours, extraterrestrial_radiation, plant_available_water,
+                  reference_et0, wind_speed_2m)
+
+
+class TestFAO56Examples(unittest.TestCase):
+    """Worked examples from FAO Irrigation and Drainage Paper 56"""
+
+    def test_extraterrestrial_radiation_example_8(self):
+        # 3 September at 20 degrees S
+        self.assertAlmostEqual(extraterrestrial_radiation(-20, date(2023, 9, 3)), 32.2, places=1)
+
+    def test_daylight_hours_example_9(self):
+        self.assertAlmostEqual(daylight_hours(-20, date(2023, 9, 3)), 11.7, places=1)
+
+    def test_wind_speed_adjustment_example_14(self):
+        # 3.2 m/s measured at 10 m
+        self.assertAlmostEqual(wind_speed_2m(3.2, 10), 2.4, places=1)
+
+    def test_reference_et0_example_18(self):
+        # Brussels (50 48'N, 100 m) on 6 July
+        result = reference_et0(date(2023, 7, 6), latitude=50 + 48 / 60, elevation=100,
+                               tmin=12.3, tmax=21.5, wind_speed=2.78, wind_height=10,
+                               rh_min=63, rh_max=84, sunshine_hours=9.25)
+        self.assertAlmostEqual(result['extraterrestrial_radiation'], 41.09, places=1)
+        self.assertAlmostEqual(result['solar_radiation'], 22.07, places=1)
+        self.assertAlmostEqual(result['net_radiation'], 13.28, places=1)
+        self.assertAlmostEqual(result['vapour_pressure_deficit'], 0.589, places=2)
+        self.assertAlmostEqual(result['et0'], 3.9, places=1)
+
+
+class TestWeatherValidation(unittest.TestCase):
+
+    def setUp(self):
+        self.inputs = dict(day=date(2023, 7, 6), latitude=40.0, elevation=1500, tmin=10.0, tmax=28.0,
+                           wind_speed=2.0, rh_mean=45, solar_radiation=25.0)
+
+    def test_valid_inputs(self):
+        self.assertGreater(reference_et0(**self.inputs)['et0'], 0)
+
+    def test_rejects_impossible_values(self):
+        for override, message in [
+            ({'tmax': 5.0}, 'tmax'),
+            ({'solar_radiation': -1.0}, 'solar_radiation'),
+            ({'rh_mean': 120}, 'rh_mean'),
+            ({'wind_speed': -0.5}, 'wind_speed'),
+            ({'latitude': 95.0}, 'latitude'),
+            ({'elevation': 50000}, 'elevation'),
+            ({'elevation': -1000}, 'elevation'),
+            ({'tmin': -237.3, 'tmax': -237.3}, 'tmin'),
+            ({'tmax': 75.0}, 'tmax'),
+            ({'rh_mean': None, 'tdew': -120.0}, 'tdew'),
+            ({'solar_radiation': None, 'sunshine_hours': 20.0}, 'sunshine_hours'),
+        ]:
+            with self.subTest(override=override):
+                with self.assertRaisesRegex(ValueError, message):
+                    reference_et0(**{**self.inputs, **override})
+
+    def test_requires_humidity_and_radiation(self):
+        with self.assertRaisesRegex(ValueError, 'rh_min/rh_max'):
+            reference_et0(**{**self.inputs, 'rh_mean': None})
+        with self.assertRaisesRegex(ValueError, 'solar_radiation or sunshine_hours'):
+            reference_et0(**{**self.inputs, 'solar_radiation': None})
+
+    def test_accepts_extreme_but_real_conditions(self):
+        # Dead Sea shore and a high Andean station
+        self.assertGreater(reference_et0(**{**self.inputs, 'elevation': -430, 'tmax': 45.0})['et0'], 0)
+        self.assertGreater(reference_et0(**{**self.inputs, 'elevation': 5000, 'tmin': -20.0})['et0'], 0)
+
+    def test_rejects_non_finite_values(self):
+        # NaN used to pass every check and come out as et0 0.0
+        for field, value in (('wind_speed', math.nan), ('solar_radiation', math.nan),
+                             ('wind_height', math.inf), ('latitude', math.nan)):
+            with self.subTest(field=field), self.assertRaises(WeatherError) as caught:
+                reference_et0(**{**self.inputs, field: value})
+            self.assertEqual(caught.exception.field, field)
+        with self.assertRaisesRegex(WeatherError, 'finite'):
+            reference_et0(**{**self.inputs, 'solar_radiation': None, 'sunshine_hours': math.nan})
+
+    def test_error_names_field_at_fault(self):
+        with self.assertRaises(WeatherError) as caught:
+            reference_et0(**{**self.inputs, 'wind_speed': -0.5})
+        self.assertEqual(caught.exception.field, 'wind_speed')
+        with self.assertRaises(WeatherError) as caught:
+            reference_et0(**{**self.inputs, 'rh_mean': None})
+        self.assertIsNone(caught.exception.field)
+
+
+class TestPlantAvailableWater(unittest.TestCase):
+
+    def test_same_moisture_differs_by_soil(self):
+        # 0.15 m3/m3 is close to wilting in clay but near field capacity in sand
+        self.assertAlmostEqual(plant_available_water(0.15, 0.40, 0.12), 0.03 / 0.28)
+        self.assertAlmostEqual(plant_available_water(0.15, 0.17, 0.05), 10 / 12)
+
+    def test_clamped_at_bounds(self):
+        self.assertEqual(plant_available_water(0.05, 0.30, 0.10), 0.0)
+        self.assertEqual(plant_available_water(0.45, 0.30, 0.10), 1.0)
+        self.assertEqual(plant_available_water(0.10, 0.30, 0.10), 0.0)
+        self.assertEqual(plant_available_water(0.30, 0.30, 0.10), 1.0)
+
+    def test_undefined_without_available_capacity(self):
+        self.assertIsNone(plant_available_water(0.2, 0.1, 0.1))
+        self.assertIsNone(plant_available_water(0.2, 0.1, 0.2))
+
+    def test_fill_values_are_missing_not_dry(self):
+        self.assertIsNone(plant_available_water(-9999.0, 0.30, 0.10))
+        self.assertIsNone(plant_available_water(-0.01, 0.30, 0.10))
+        self.assertIsNone(plant_available_water(1.5, 0.30, 0.10))
+        self.assertIsNone(plant_available_water(0.2, 0.30, -9999.0))
+
+
+if __name__ == '__main__':
+    unittest.main()
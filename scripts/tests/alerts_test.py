@@ -0,0 +1,78 @@
+import unittest
+import sys
+import os
+import sqlite3
+from email import message_from_string
+from unittest import mock
+
+# Add the parent directory to the Python path
+sys.path.append(os.path.dirname(os.path.dirname(os.path.abspath(__file__))))
+
+import alerts
+import anomaly
+
+SMTP_ENV = {'OPENFLOW_SMTP_HOST': 'smtp.example.com', 'OPENFLOW_SMTP_FROM': 'openflow@example.com',
+            'OPENFLOW_SMTP_TO': 'ops@example.com'}
+HOUR = 3600
+
+
+class TestAlerts(unittest.TestCase):
+
+    def setUp(self):
+        self.conn = sqlite3.connect(':memory:')
+        self.conn.execute('CREATE TABLE processed_data (date TEXT, location TEXT, smap_value REAL, vegdri_value REAL)')
+        patcher = mock.patch.dict(os.environ, SMTP_ENV)
+        patcher.start()
+        self.addCleanup(patcher.stop)
+
+    def tearDown(self):
+        self.conn.close()
+
+    def outbox(self):
+        return [message_from_string(row[0]) for row in
+                self.conn.execute("SELECT message FROM outbox WHERE kind = 'moisture_alert' ORDER BY id")]
+
+    def test_cooldown_limits_repeats(self):
+        alerts.add(self.conn, 'Vineyard', 'below', 0.12, cooldown_hours=72, recipients='grower@example.com')
+        for day in range(1, 8):
+            with self.conn:
+                alerts.evaluate(self.conn, [(f'2024-07-{day:02d}', 'Vineyard', 0.10, None)], now=day * 24 * HOUR)
+        messages = self.outbox()
+        # Days 1, 4 and 7: each 72 hours after the last email
+        self.assertEqual(len(messages), 3)
+        self.assertEqual(messages[0]['To'], 'grower@example.com')
+        self.assertIn('Vineyard', messages[0]['Subject'])
+        self.assertIn('0.100 m3/m3 on 2024-07-01', messages[0].get_payload())
+
+    def test_newest_valid_observation_decides(self):
+        alerts.add(self.conn, 'A', 'below', 0.12)
+        alerts.add(self.conn, 'B', 'above', 0.40)
+        alerts.evaluate(self.conn, [('2024-07-01', 'A', 0.05, None), ('2024-07-02', 'A', 0.20, None),
+                                    ('2024-07-03', 'A', -9999.0, None), ('2024-07-02', 'B', 0.45, None)])
+        messages = self.outbox()
+        self.assertEqual([message['To'] for message in messages], ['ops@example.com'])
+        self.assertIn('B soil moisture above 0.4', messages[0]['Subject'])
+
+    def test_nothing_queued_without_email(self):
+        alerts.add(self.conn, 'A', 'below', 0.12)
+        with mock.patch.dict(os.environ, {'OPENFLOW_SMTP_HOST': ''}):
+            self.assertEqual(alerts.evaluate(self.conn, [('2024-07-01', 'A', 0.05, None)]), 0)
+        # The cooldown only starts once an email is actually queued
+        self.assertEqual(alerts.evaluate(self.conn, [('2024-07-01', 'A', 0.05, None)]), 1)
+
+    def test_published_runs_are_evaluated(self):
+        alerts.add(self.conn, 'A', 'below', 0.12)
+        anomaly.ingest(self.conn, [('2024-07-01', 'A', 0.05, None)])
+        self.assertEqual(len(self.outbox()), 1)
+
+    def test_invalid_rules(self):
+        for args in (('', 'below', 0.1), ('A', 'equals', 0.1), ('A', 'below', 12)):
+            with self.subTest(args=args), self.assertRaises(ValueError):
+                alerts.add(self.conn, *args)
+        with self.assertRaises(ValueError):
+            alerts.add(self.conn, 'A', 'below', 0.1, recipients='grower@example.com, nobody')
+        self.assertFalse(alerts.remove(self.conn, 99))
+
+
+if __name__ == '__main__':
+    unittest.main()
@@ -0,0 +1,103 @@
+import unittest
+import sys
+import os
+import sqlite3
+from unittest import mock
+
+# Add the parent directory to the Python path
+sys.path.append(os.path.dirname(os.path.dirname(os.path.abspath(__file__))))
+
+import anomaly
+import notify
+
+SMTP_ENV = {'OPENFLOW_SMTP_HOST': 'smtp.example.com', 'OPENFLOW_SMTP_FROM': 'openflow@example.com',
+            'OPENFLOW_SMTP_TO': 'ops@example.com'}
+LOCATIONS = ['USGS:09085000', 'USGS:09095500', 'DWR:CLAGLECO', 'DWR:ARKCATCO']
+
+
+class TestAnomaly(unittest.TestCase):
+
+    def setUp(self):
+        self.conn = sqlite3.connect(':memory:')
+        self.conn.execute('CREATE TABLE processed_data (date TEXT, location TEXT, smap_value REAL, vegdri_value REAL)')
+
+    def tearDown(self):
+        self.conn.close()
+
+    def run_rows(self, day, shift=0.0, locations=LOCATIONS, fill=0):
+        rows = [(f'2024-05-{day:02d}', location, 0.2 + 0.01 * i + shift, None)
+                for i, location in enumerate(locations)]
+        return rows + [(f'2024-05-{day:02d}', 'USGS:FILL', -9999.0, None)] * fill
+
+    def published(self):
+        return self.conn.execute('SELECT COUNT(*) FROM processed_data').fetchone()[0]
+
+    def build_history(self, runs=3):
+        for day in range(1, runs + 1):
+            self.assertEqual(anomaly.ingest(self.conn, self.run_rows(day))[1], 'accepted')
+
+    def test_accepts_without_enough_history(self):
+        self.build_history(2)
+        _, status, _ = anomaly.ingest(self.conn, self.run_rows(3, shift=0.2))
+        self.assertEqual(status, 'accepted')
+        self.assertEqual(self.published(), 12)
+
+    def test_shifted_mean_is_quarantined(self):
+        self.build_history()
+        run_id, status, reasons = anomaly.ingest(self.conn, self.run_rows(4, shift=0.2))
+        self.assertEqual(status, 'quarantined')
+        self.assertIn('mean', reasons[0])
+        self.assertEqual(self.published(), 12)
+
+        # Quarantined runs don't move the baseline
+        self.assertEqual(anomaly.ingest(self.conn, self.run_rows(5, shift=0.2))[1], 'quarantined')
+        self.assertEqual(anomaly.ingest(self.conn, self.run_rows(6, shift=0.01))[1], 'accepted')
+
+    def test_coverage_and_valid_fraction_drops(self):
+        self.build_history()
+        _, _, reasons = anomaly.ingest(self.conn, self.run_rows(4, locations=LOCATIONS[:1], fill=3))
+        self.assertEqual(len(reasons), 2)
+        self.assertTrue(any('valid fraction' in reason for reason in reasons))
+        self.assertTrue(any('locations' in reason for reason in reasons))
+
+    def test_approve_publishes_rows(self):
+        self.build_history()
+        run_id, _, _ = anomaly.ingest(self.conn, self.run_rows(4, shift=0.2))
+        self.assertEqual(anomaly.approve(self.conn, run_id), 4)
+        self.assertEqual(self.published(), 16)
+        self.assertEqual(self.conn.execute('SELECT COUNT(*) FROM quarantined_data').fetchone()[0], 0)
+        with self.assertRaises(ValueError):
+            anomaly.approve(self.conn, run_id)
+
+    def test_rollback_discards_rows(self):
+        self.build_history()
+        run_id, _, _ = anomaly.ingest(self.conn, self.run_rows(4, shift=0.2))
+        self.assertEqual(anomaly.rollback(self.conn, run_id), 4)
+        self.assertEqual(self.published(), 12)
+        self.assertEqual(self.conn.execute('SELECT status FROM ingestion_runs WHERE id = ?', (run_id,)).fetchone(),
+                         ('rolled_back',))
+        with self.assertRaises(ValueError):
+            anomaly.rollback(self.conn, 999)
+
+    def outbox(self):
+        return self.conn.execute("SELECT kind FROM outbox WHERE state = 'pending'").fetchall()
+
+    def test_quarantine_alert_is_queued_with_the_run(self):
+        self.build_history()
+        with mock.patch.dict(os.environ, SMTP_ENV):
+            anomaly.ingest(self.conn, self.run_rows(4, shift=0.2))
+        self.assertEqual(self.outbox(), [('ingestion_quarantined',)])
+
+    def test_failed_ingest_leaves_no_alert(self):
+        self.build_history()
+        notify.create_outbox(self.conn)
+        self.conn.execute("""CREATE TRIGGER fail_quarantine BEFORE INSERT ON quarantined_data
+                             BEGIN SELECT RAISE(ABORT, 'disk I/O error'); END""")
+        with mock.patch.dict(os.environ, SMTP_ENV), self.assertRaises(sqlite3.DatabaseError):
+            anomaly.ingest(self.conn, self.run_rows(4, shift=0.2))
+        self.assertEqual(self.outbox(), [])
+        self.assertEqual(self.conn.execute('SELECT COUNT(*) FROM ingestion_runs').fetchone()[0], 3)
+
+
+if __name__ == '__main__':
+    unittest.main()
@@ -0,0 +1,96 @@
+import unittest
+import sys
+import os
+import sqlite3
+from datetime import date, timedelta
+
+# Add the parent directory to the Python path
+sys.path.append(os.path.dirname(os.path.dirname(os.path.abspath(__file__))))
+
+import climatology
+
+
+class TestBaselines(unittest.TestCase):
+
+    def test_day_of_year_lines_up_across_leap_years(self):
+        self.assertEqual(climatology.day_of_year(date(2024, 3, 1)), climatology.day_of_year(date(2023, 3, 1)))
+        self.assertEqual(climatology.day_of_year(date(2024, 2, 29)), 59)
+        self.assertEqual(climatology.day_of_year(date(2024, 12, 31)), 365)
+
+    def test_quantile_interpolates(self):
+        self.assertEqual(climatology.quantile([1, 2, 3, 4, 5], 50), 3)
+        self.assertAlmostEqual(climatology.quantile([0, 10], 25), 2.5)
+        self.assertEqual(climatology.quantile([7], 90), 7)
+
+    def test_window_pools_neighbouring_days_across_years(self):
+        observations = {date(2020 + year, 6, 10) + timedelta(days=offset): 0.1 * (year + 1)
+                        for year in range(3) for offset in range(-7, 8)}
+        stats = climatology.baselines(observations)
+        june_10 = climatology.day_of_year(date(2021, 6, 10))
+        self.assertEqual(stats[june_10]['observations'], 45)
+        self.assertEqual(stats[june_10]['years'], 3)
+        self.assertAlmostEqual(stats[june_10]['mean'], 0.2)
+        self.assertAlmostEqual(stats[june_10]['p50'], 0.2)
+        # One day further out, the window loses a day of each year
+        self.assertEqual(stats[june_10 + 1]['observations'], 42)
+
+    def test_window_wraps_new_year(self):
+        observations = {date(2022, 12, 25) + timedelta(days=offset): 0.3 for offset in range(14)}
+        stats = climatology.baselines(observations)
+        self.assertIn(1, stats)
+        self.assertIn(365, stats)
+
+    def test_percentile_of_inverts_quantiles(self):
+        quantiles = {q: q / 100 for q in climatology.QUANTILES}
+        self.assertAlmostEqual(climatology.percentile_of(0.15, quantiles), 15)
+        self.assertEqual(climatology.percentile_of(-0.5, quantiles), 0)
+        self.assertEqual(climatology.percentile_of(1.5, quantiles), 100)
+        self.assertIsNone(climatology.percentile_of(0.2, {0: 0.1, 50: None, 100: 0.3}))
+
+    def test_percentile_of_flat_stretch_is_its_middle(self):
+        self.assertEqual(climatology.percentile_of(0.1, {0: 0.1, 50: 0.1, 100: 0.3}), 25)
+
+    def test_sparse_days_are_skipped(self):
+        self.assertEqual(climatology.baselines({date(2023, 6, 1): 0.2}), {})
+
+
+class TestUpdate(unittest.TestCase):
+
+    def setUp(self):
+        self.conn = sqlite3.connect(':memory:')
+        self.conn.execute('CREATE TABLE processed_data (date TEXT, location TEXT, smap_value REAL, vegdri_value REAL)')
+        self.add('A', date(2023, 5, 1), 30)
+        self.add('B', date(2023, 5, 1), 30)
+
+    def tearDown(self):
+        self.conn.close()
+
+    def add(self, location, start, days, value=0.25):
+        with self.conn:
+            self.conn.executemany('INSERT INTO processed_data VALUES (?, ?, ?, NULL)', [
+                ((start + timedelta(days=i)).isoformat(), location, value) for i in range(days)])
+
+    def test_only_changed_locations_are_recomputed(self):
+        self.assertEqual(climatology.update(self.conn), 2)
+        self.assertEqual(climatology.update(self.conn), 0)
+        self.add('B', date(2023, 6, 1), 1)
+        self.assertEqual(climatology.stale_locations(self.conn), ['B'])
+        self.assertEqual(climatology.update(self.conn), 1)
+        self.assertEqual(climatology.update(self.conn, full=True), 2)
+
+    def test_new_quantile_columns_are_migrated_and_recomputed(self):
+        climatology.update(self.conn)
+        self.conn.execute('ALTER TABLE climatology DROP COLUMN p2')
+        self.assertEqual(climatology.update(self.conn), 2)
+        self.assertIsNotNone(self.conn.execute("SELECT p2 FROM climatology LIMIT 1").fetchone()[0])
+
+    def test_fill_values_are_ignored(self):
+        self.add('A', date(2023, 5, 1), 30, value=-9999.0)
+        climatology.update(self.conn)
+        means = {row[0] for row in self.conn.execute("SELECT mean FROM climatology WHERE location = 'A'")}
+        self.assertEqual(len(means), 1)
+        self.assertAlmostEqual(means.pop(), 0.25)
+
+
+if __name__ == '__main__':
+    unittest.main()
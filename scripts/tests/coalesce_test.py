@@ -0,0 +1,78 @@
+import unittest
+import sys
+import os
+import threading
+
+# Add the parent directory to the Python path
+sys.path.append(os.path.dirname(os.path.dirname(os.path.abspath(__file__))))
+
+from coalesce import SingleFlight
+
+CALLERS = 10
+
+
+class TestSingleFlight(unittest.TestCase):
+
+    def run_concurrently(self, flights, key, fn):
+        """Start CALLERS threads on the same key while fn is blocked, then release it"""
+        results, errors = [], []
+
+        def call():
+            try:
+                results.append(flights.do(key, fn))
+            except Exception as e:
+                errors.append(e)
+        threads = [threading.Thread(target=call) for _ in range(CALLERS)]
+        for thread in threads:
+            thread.start()
+        return threads, results, errors
+
+    def wait_for_followers(self, flights):
+        for _ in range(500):
+            if flights.coalesced == CALLERS - 1:
+                return
+            threading.Event().wait(0.01)
+        self.fail("followers never arrived")
+
+    def test_one_computation_for_identical_calls(self):
+        flights, release, calls = SingleFlight(), threading.Event(), []
+
+        def storage():
+            calls.append(1)
+            release.wait(5)
+            return {'rows': 3}
+        threads, results, errors = self.run_concurrently(flights, 'same', storage)
+        self.wait_for_followers(flights)
+        release.set()
+        for thread in threads:
+            thread.join()
+
+        self.assertEqual(len(calls), 1)
+        self.assertEqual(results, [{'rows': 3}] * CALLERS)
+        self.assertEqual(errors, [])
+        self.assertEqual((flights.leaders, flights.coalesced), (1, CALLERS - 1))
+
+    def test_failed_leader_does_not_poison_later_calls(self):
+        flights, release = SingleFlight(), threading.Event()
+
+        def failing():
+            release.wait(5)
+            raise RuntimeError("database locked")
+        threads, results, errors = self.run_concurrently(flights, 'same', failing)
+        self.wait_for_followers(flights)
+        release.set()
+        for thread in threads:
+            thread.join()
+
+        self.assertEqual(len(errors), CALLERS)
+        self.assertEqual(flights.do('same', lambda: 'recovered'), 'recovered')
+
+    def test_different_keys_run_separately(self):
+        flights = SingleFlight()
+        self.assertEqual(flights.do('a', lambda: 1), 1)
+        self.assertEqual(flights.do('b', lambda: 2), 2)
+        self.assertEqual((flights.leaders, flights.coalesced), (2, 0))
+
+
+if __name__ == '__main__':
+    unittest.main()
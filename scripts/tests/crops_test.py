@@ -0,0 +1,63 @@
+import unittest
+import sys
+import os
+import sqlite3
+
+# Add the parent directory to the Python path
+sys.path.append(os.path.dirname(os.path.dirname(os.path.abspath(__file__))))
+
+import crops
+
+
+class TestCrops(unittest.TestCase):
+
+    def setUp(self):
+        self.conn = sqlite3.connect(':memory:')
+
+    def tearDown(self):
+        self.conn.close()
+
+    def row(self, **overrides):
+        row = {'crop': 'Corn', 'stage': 'tasseling', 'paw_lower': '0.6', 'paw_upper': '1.0',
+               'vwc_lower': '0.2', 'vwc_upper': '0.3'}
+        row.update(overrides)
+        return row
+
+    def test_presets_follow_depletion_fraction(self):
+        crops.create_table(self.conn)
+        corn = crops.lookup(self.conn, 'CORN')
+        self.assertAlmostEqual(corn['paw_lower'], 0.45)
+        self.assertEqual(corn['paw_upper'], 1.0)
+        self.assertAlmostEqual(corn['vwc_lower'], 0.12 + 0.45 * 0.13, places=4)
+        self.assertIn('potato', crops.available(self.conn))
+
+    def test_edits_survive_reseeding(self):
+        self.assertEqual(crops.create_table(self.conn), len(crops.PRESETS))
+        crops.load_bands(self.conn, [self.row(stage='', paw_lower='0.5')])
+        # Nothing to add on a rerun, so the deploy reports no change
+        self.assertEqual(crops.create_table(self.conn), 0)
+        self.assertEqual(crops.lookup(self.conn, 'corn')['paw_lower'], 0.5)
+
+    def test_stages_listed_with_crop(self):
+        self.assertEqual(crops.load_bands(self.conn, [self.row()]), 1)
+        self.assertIn('corn:tasseling', crops.available(self.conn))
+        self.assertIsNotNone(crops.lookup(self.conn, 'corn', 'tasseling'))
+
+    def test_invalid_rows_write_nothing(self):
+        with self.assertRaisesRegex(ValueError, 'line 3: vwc_lower must be below vwc_upper'):
+            crops.load_bands(self.conn, [self.row(), self.row(vwc_lower='0.4')])
+        with self.assertRaisesRegex(ValueError, 'line 2: paw_upper must be between 0 and 1'):
+            crops.load_bands(self.conn, [self.row(paw_upper='1.5')])
+        self.assertEqual(crops.available(self.conn), [])
+
+    def test_missing_table(self):
+        self.assertIsNone(crops.lookup(self.conn, 'corn'))
+        self.assertEqual(crops.available(self.conn), [])
+
+    def test_classify_inclusive_band(self):
+        self.assertEqual([crops.classify(v, 0.2, 0.3) for v in (0.1, 0.2, 0.3, 0.31)],
+                         ['below', 'within', 'within', 'above'])
+
+
+if __name__ == '__main__':
+    unittest.main()
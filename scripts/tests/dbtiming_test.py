@@ -0,0 +1,59 @@
+import unittest
+import sys
+import os
+
+# Add the parent directory to the Python path
+sys.path.append(os.path.dirname(os.path.dirname(os.path.abspath(__file__))))
+
+import dbtiming
+
+ENDLESS = 'WITH RECURSIVE r(x) AS (SELECT 1 UNION ALL SELECT x + 1 FROM r) SELECT COUNT(*) FROM r'
+
+
+class TestSlowQueryLog(unittest.TestCase):
+
+    def setUp(self):
+        self.conn = dbtiming.connect(':memory:')
+        self.conn.slow_query_ms = 0
+        self.conn.execute('CREATE TABLE t (location TEXT, lat REAL)')
+        self.conn.executemany('INSERT INTO t VALUES (?, ?)', [('A', 39.123456), ('B', 40.987654)])
+
+    def tearDown(self):
+        self.conn.close()
+
+    def test_logs_statement_rows_and_rounded_params(self):
+        with self.assertLogs('dbtiming', level='WARNING') as logs:
+            self.conn.execute('SELECT * FROM t\n   WHERE lat > ?', (39.123456,)).fetchall()
+        self.assertEqual(len(logs.output), 1)
+        self.assertIn('1 rows', logs.output[0])
+        self.assertIn('SELECT * FROM t WHERE lat > ?', logs.output[0])
+        self.assertIn('(39.12,)', logs.output[0])
+
+    def test_counts_rows_when_iterated(self):
+        with self.assertLogs('dbtiming', level='WARNING') as logs:
+            list(self.conn.execute('SELECT * FROM t'))
+        self.assertIn('2 rows', logs.output[0])
+
+    def test_fast_queries_are_not_logged(self):
+        self.conn.slow_query_ms = 60000
+        with self.assertNoLogs('dbtiming', level='WARNING'):
+            self.conn.execute('SELECT COUNT(*) FROM t').fetchone()
+
+
+class TestBudget(unittest.TestCase):
+
+    def test_runaway_query_is_interrupted(self):
+        conn = dbtiming.connect(':memory:', budget_ms=50)
+        with self.assertRaises(dbtiming.QueryBudgetExceeded):
+            conn.execute(ENDLESS).fetchone()
+        conn.close()
+
+    def test_budget_can_be_disabled(self):
+        conn = dbtiming.connect(':memory:', budget_ms=None)
+        self.assertEqual(conn.execute('SELECT 1').fetchone(), (1,))
+        self.assertFalse(conn.budget_exceeded)
+        conn.close()
+
+
+if __name__ == '__main__':
+    unittest.main()
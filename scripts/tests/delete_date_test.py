@@ -0,0 +1,61 @@
+import unittest
+import sys
+import os
+import sqlite3
+from datetime import date, timedelta
+
+# Add the parent directory to the Python path
+sys.path.append(os.path.dirname(os.path.dirname(os.path.abspath(__file__))))
+
+import anomaly
+import climatology
+import integrity
+from delete_date import NoSuchDate, delete_date, preview
+
+
+class TestDeleteDate(unittest.TestCase):
+
+    def setUp(self):
+        self.conn = sqlite3.connect(':memory:')
+        self.conn.execute('CREATE TABLE processed_data (date TEXT, location TEXT, smap_value REAL, vegdri_value REAL)')
+        for i in range(15):
+            day = (date(2024, 5, 1) + timedelta(days=i)).isoformat()
+            anomaly.ingest(self.conn, [(day, location, 0.2 + 0.001 * i, None) for location in 'AB'])
+        # A corrupt date, with a duplicated row; stored directly as the anomaly check would hold it back
+        with self.conn:
+            self.conn.executemany('INSERT INTO processed_data VALUES (?, ?, ?, NULL)',
+                                  [('2024-05-16', location, 0.95) for location in 'ABB'])
+            integrity.record(self.conn, ['2024-05-16'])
+        climatology.update(self.conn)
+
+    def tearDown(self):
+        self.conn.close()
+
+    def count(self, query, *params):
+        return self.conn.execute(query, params).fetchone()[0]
+
+    def test_preview_changes_nothing(self):
+        self.assertEqual(preview(self.conn, '2024-05-16'), {'date': '2024-05-16', 'rows': 3, 'locations': 2})
+        self.assertEqual(self.count("SELECT COUNT(*) FROM processed_data WHERE date = '2024-05-16'"), 3)
+
+    def test_delete_in_batches(self):
+        self.assertEqual(delete_date(self.conn, '2024-05-16', 'corrupt granule', batch_rows=2), 3)
+        self.assertEqual(self.count("SELECT COUNT(*) FROM processed_data WHERE date = '2024-05-16'"), 0)
+        self.assertEqual(self.count('SELECT COUNT(*) FROM processed_data'), 30)
+        # Verification no longer expects the date, and the baselines no longer include it
+        self.assertEqual(self.count("SELECT COUNT(*) FROM date_checksums WHERE date = '2024-05-16'"), 0)
+        self.assertEqual(integrity.verify(self.conn, sample_rate=1.0, pause=0), [])
+        self.assertEqual(climatology.stale_locations(self.conn), [])
+        self.assertLess(self.count("SELECT MAX(p100) FROM climatology"), 0.9)
+        self.assertEqual(self.conn.execute('SELECT date, row_count, locations, reason FROM deletions').fetchall(),
+                         [('2024-05-16', 3, 2, 'corrupt granule')])
+
+    def test_missing_date(self):
+        with self.assertRaises(NoSuchDate):
+            delete_date(self.conn, '2024-06-01')
+        with self.assertRaises(NoSuchDate):
+            preview(self.conn, '2024-06-01')
+
+
+if __name__ == '__main__':
+    unittest.main()
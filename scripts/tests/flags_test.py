@@ -0,0 +1,56 @@
+import unittest
+import sys
+import os
+
+# Add the parent directory to the Python path
+sys.path.append(os.path.dirname(os.path.dirname(os.path.abspath(__file__))))
+
+from flags import (FILL_VALUE, RECOMMENDED_EXCLUDE, SurfaceFlag, decode, excluded,
+                   exclusion_mask)
+
+
+class TestSurfaceFlags(unittest.TestCase):
+
+    def test_documented_bit_positions(self):
+        positions = {
+            'STATIC_WATER': 0, 'RADAR_WATER': 1, 'COASTAL_PROXIMITY': 2, 'URBAN_AREA': 3,
+            'PRECIPITATION': 4, 'SNOW_OR_ICE': 5, 'PERMANENT_SNOW_OR_ICE': 6,
+            'RADIOMETER_FROZEN_GROUND': 7, 'MODEL_FROZEN_GROUND': 8, 'MOUNTAINOUS_TERRAIN': 9,
+            'DENSE_VEGETATION': 10, 'NADIR_REGION': 11
+        }
+        for name, bit in positions.items():
+            self.assertEqual(SurfaceFlag[name], 1 << bit)
+
+    def test_decode(self):
+        # Coastal (bit 2), mountainous (bit 9) and dense vegetation (bit 10)
+        decoded = decode(0b110_0000_0100)
+        self.assertTrue(decoded['coastal_proximity'])
+        self.assertTrue(decoded['mountainous_terrain'])
+        self.assertTrue(decoded['dense_vegetation'])
+        self.assertEqual(sum(decoded.values()), 3)
+        self.assertFalse(any(decode(0).values()))
+
+    def test_fill_value_is_unknown(self):
+        self.assertIsNone(decode(FILL_VALUE))
+        self.assertIsNone(decode(None))
+        self.assertFalse(excluded(FILL_VALUE, RECOMMENDED_EXCLUDE))
+
+    def test_exclusion_groups(self):
+        water = exclusion_mask(['water'])
+        self.assertTrue(excluded(SurfaceFlag.RADAR_WATER, water))
+        self.assertFalse(excluded(SurfaceFlag.COASTAL_PROXIMITY, water))
+        frozen = exclusion_mask(['frozen', 'dense_vegetation'])
+        self.assertTrue(excluded(SurfaceFlag.MODEL_FROZEN_GROUND, frozen))
+        self.assertTrue(excluded(SurfaceFlag.DENSE_VEGETATION, frozen))
+        self.assertEqual(exclusion_mask([]), 0)
+        with self.assertRaises(ValueError):
+            exclusion_mask(['clouds'])
+
+    def test_recommended_keeps_mountains_and_coasts(self):
+        self.assertFalse(excluded(SurfaceFlag.MOUNTAINOUS_TERRAIN | SurfaceFlag.COASTAL_PROXIMITY,
+                                  RECOMMENDED_EXCLUDE))
+        self.assertTrue(excluded(SurfaceFlag.STATIC_WATER, RECOMMENDED_EXCLUDE))
+
+
+if __name__ == '__main__':
+    unittest.main()
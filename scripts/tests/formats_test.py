@@ -0,0 +1,104 @@
+import json
+import unittest
+import sys
+import os
+
+# Add the parent directory to the Python path
+sys.path.append(os.path.dirname(os.path.dirname(os.path.abspath(__file__))))
+
+from formats import JSON, MSGPACK, encode, negotiate, round_significant
+
+try:
+    import msgpack
+except ImportError:
+    msgpack = None
+
+
+class TestNegotiation(unittest.TestCase):
+
+    def test_defaults_to_json(self):
+        self.assertEqual(negotiate(None, None), JSON)
+        self.assertEqual(negotiate(None, '*/*'), JSON)
+
+    def test_accept_header(self):
+        self.assertEqual(negotiate(None, 'application/msgpack'), MSGPACK)
+        self.assertEqual(negotiate(None, 'application/x-msgpack, application/json;q=0.5'), MSGPACK)
+
+    def test_q_values(self):
+        # q=0 means "not acceptable", however the range is spelled
+        self.assertEqual(negotiate(None, 'application/msgpack;q=0'), JSON)
+        self.assertEqual(negotiate(None, 'application/json, application/msgpack; q=0'), JSON)
+        self.assertEqual(negotiate(None, 'application/msgpack;q=0.4, application/json;q=0.8'), JSON)
+        self.assertEqual(negotiate(None, 'application/json;q=0.2, application/vnd.msgpack;q=0.9'), MSGPACK)
+        self.assertEqual(negotiate(None, 'application/json;q=0, */*'), MSGPACK)
+        # An explicit refusal isn't overridden by a wildcard
+        self.assertEqual(negotiate(None, 'application/msgpack;q=0, */*'), JSON)
+        self.assertEqual(negotiate(None, 'application/msgpack;q=0, application/json;q=0, */*'), JSON)
+        self.assertEqual(negotiate(None, 'application/x-msgpack;q=0, application/msgpack;q=0.5, */*;q=0.4'),
+                         MSGPACK)
+
+    def test_malformed_accept(self):
+        self.assertEqual(negotiate(None, 'application/msgpack;q=high'), JSON)
+        self.assertEqual(negotiate(None, ',;, '), JSON)
+
+    def test_format_parameter_wins(self):
+        self.assertEqual(negotiate('json', 'application/msgpack'), JSON)
+        self.assertEqual(negotiate('msgpack', None), MSGPACK)
+
+    def test_unknown_format(self):
+        with self.assertRaises(ValueError):
+            negotiate('xml', None)
+
+
+class TestSignificantDigits(unittest.TestCase):
+
+    def test_renders_widened_float32_values(self):
+        for value, digits, rendered in [
+            (0.23000000417232513, 7, '0.23'),
+            (0.23000000417232513, 3, '0.23'),
+            (0.123456789, 3, '0.123'),
+            (23.000000417232513, 7, '23.0'),
+            (0.0, 7, '0.0'),
+            (1.2345678901e-05, 7, '1.234568e-05'),
+            (2.0000000233e-09, 7, '2e-09'),
+            (-9999.0, 7, '-9999.0'),
+            (-9998.999999999, 7, '-9999.0'),
+            (1.0000001, 7, '1.0'),
+            (0.99999999, 7, '1.0'),
+        ]:
+            with self.subTest(value=value, digits=digits):
+                self.assertEqual(encode(value, JSON, digits), rendered)
+
+    def test_rounds_nested_floats_only(self):
+        payload = {'data': [{'smap_value': 0.21300000071525574, 'count': 3, 'filled': True, 'location': 'A'}],
+                   'center': (35.123456789, -106.5)}
+        self.assertEqual(encode(payload, JSON, 7),
+                         '{"data": [{"smap_value": 0.213, "count": 3, "filled": true, "location": "A"}], '
+                         '"center": [35.12346, -106.5]}')
+
+    def test_zero_or_none_digits_leave_values_alone(self):
+        self.assertEqual(encode(0.23000000417232513, JSON), '0.23000000417232513')
+        self.assertEqual(round_significant(0.23000000417232513, 0), 0.23000000417232513)
+        self.assertIsNone(round_significant(None, 7))
+
+
+@unittest.skipIf(msgpack is None, "msgpack not installed")
+class TestMsgpackRoundTrip(unittest.TestCase):
+
+    def test_matches_json_output(self):
+        payload = {
+            'meta': {'units': 'm3/m3', 'count': 2, 'truncated': False, 'latest_date': None},
+            'data': [{'date': '2023-01-01', 'location': 'A', 'smap_value': 0.213, 'filled': True},
+                     {'date': '2023-01-02', 'location': 'A', 'smap_value': None, 'filled': False}]
+        }
+        decoded = msgpack.unpackb(encode(payload, MSGPACK), raw=False)
+        self.assertEqual(decoded, json.loads(encode(payload, JSON)))
+
+    def test_rounds_like_json(self):
+        payload = {'smap_value': 0.23000000417232513, 'tiny': 1.2345678901e-05}
+        self.assertEqual(msgpack.unpackb(encode(payload, MSGPACK, 7), raw=False),
+                         json.loads(encode(payload, JSON, 7)))
+
+
+if __name__ == '__main__':
+    unittest.main()
@@ -0,0 +1,68 @@
+import unittest
+import sys
+import os
+import tempfile
+from datetime import datetime
+from pathlib import Path
+from unittest.mock import MagicMock, patch
+
+# Add the parent directory to the Python path
+sys.path.append(os.path.dirname(os.path.dirname(os.path.abspath(__file__))))
+
+import granules
+
+NAME = 'SMAP_L3_SM_P_E_20240305_R19240_001.h5'
+
+
+class TestGranuleSources(unittest.TestCase):
+
+    def setUp(self):
+        self.tmp = tempfile.TemporaryDirectory()
+        self.dest = Path(self.tmp.name) / 'dest'
+        self.dest.mkdir()
+
+    def tearDown(self):
+        self.tmp.cleanup()
+
+    def test_parse_keeps_order(self):
+        spec = 'https://mirror.example.org/{version}/{yyyy}/{mm}/{dd}/{granule}, earthdata'
+        self.assertEqual(granules.parse_sources(spec),
+                         ['https://mirror.example.org/{version}/{yyyy}/{mm}/{dd}/{granule}', 'earthdata'])
+
+    def test_rejects_malformed_templates(self):
+        for spec in ('', 'ftp://mirror/{granule}', 'https://mirror/{year}/{granule}',
+                     'https://mirror/{yyyy}', 'https://mirror/{granule', 'nsidc'):
+            with self.assertRaises(ValueError, msg=spec):
+                granules.parse_sources(spec)
+
+    def test_expand(self):
+        url = granules.expand('s3://bucket/SPL3SMP_E.{version}/{yyyy}.{mm}.{dd}/{granule}',
+                              datetime(2024, 3, 5), '006', NAME)
+        self.assertEqual(url, f's3://bucket/SPL3SMP_E.006/2024.03.05/{NAME}')
+
+    def test_fetch_file(self):
+        source = Path(self.tmp.name) / NAME
+        source.write_bytes(b'granule')
+        target = granules.fetch(source.as_uri(), self.dest)
+        self.assertEqual(target, self.dest / NAME)
+        self.assertEqual(target.read_bytes(), b'granule')
+
+    def test_failed_fetch_leaves_nothing(self):
+        with self.assertRaises(OSError):
+            granules.fetch(f'file:///nonexistent/{NAME}', self.dest)
+        self.assertEqual(list(self.dest.iterdir()), [])
+
+    def test_fetch_https(self):
+        response = MagicMock()
+        response.__enter__.return_value = response
+        response.iter_content.return_value = [b'gran', b'ule']
+        session = MagicMock()
+        session.get.return_value = response
+        with patch.object(granules.http_client, 'session', return_value=session):
+            target = granules.fetch(f'https://mirror.example.org/2024/{NAME}', self.dest)
+        self.assertEqual(target.read_bytes(), b'granule')
+        response.raise_for_status.assert_called_once()
+
+
+if __name__ == '__main__':
+    unittest.main()
@@ -0,0 +1,86 @@
+import unittest
+import sys
+import os
+
+# Add the parent directory to the Python path
+sys.path.append(os.path.dirname(os.path.dirname(os.path.abspath(__file__))))
+
+from grid import GRIDS, forward, inverse
+
+
+class TestProjection(unittest.TestCase):
+
+    def test_published_grid_extent(self):
+        # NSIDC: map corners at x = +/-17367530.45 m, y = +/-7314540.83 m, ~85.0445664 N
+        x, y = forward(85.0445664, 180)
+        self.assertAlmostEqual(x, 17367530.45, delta=0.01)
+        self.assertAlmostEqual(y, 7314540.83, delta=0.1)
+        for grid in GRIDS.values():
+            self.assertAlmostEqual(grid.origin[0], -17367530.45, delta=0.01)
+            self.assertAlmostEqual(grid.origin[1], 7314540.83, delta=0.01)
+
+    def test_round_trip(self):
+        for lat, lon in ((0, 0), (39.55, -107.32), (-33.9, 151.2), (84.9, 179.9), (-70.0, -45.5)):
+            back_lat, back_lon = inverse(*forward(lat, lon))
+            self.assertAlmostEqual(back_lat, lat, places=6)
+            self.assertAlmostEqual(back_lon, lon, places=9)
+
+
+class TestGrid(unittest.TestCase):
+
+    def test_reference_cell_centers(self):
+        # First cell centers in the NSIDC EASE2_M36km/M09km latitude and longitude files
+        lat, lon = GRIDS['M36'].center(0, 0)
+        self.assertAlmostEqual(lat, 83.63197, places=4)
+        self.assertAlmostEqual(lon, -179.81328, places=4)
+        lat, lon = GRIDS['M09'].center(0, 0)
+        self.assertAlmostEqual(lat, 84.65642, places=4)
+        self.assertAlmostEqual(lon, -179.95332, places=4)
+
+    def test_equator_and_prime_meridian_are_cell_edges(self):
+        m36 = GRIDS['M36']
+        self.assertEqual(m36.cell(0.0001, 0.0001), (202, 482))
+        self.assertEqual(m36.cell(-0.0001, -0.0001), (203, 481))
+
+    def test_cell_contains_point(self):
+        for grid in GRIDS.values():
+            row, col = grid.cell(39.55, -107.32)
+            bounds = grid.bounds(row, col)
+            self.assertTrue(bounds['south'] <= 39.55 < bounds['north'])
+            self.assertTrue(bounds['west'] <= -107.32 < bounds['east'])
+            corners = grid.corners(row, col)
+            self.assertEqual(corners[0], (bounds['north'], bounds['west']))
+
+    def test_index_round_trip_across_hemispheres(self):
+        for grid in GRIDS.values():
+            for row in (0, grid.rows // 2 - 1, grid.rows // 2, grid.rows - 1):
+                for col in (0, grid.cols // 2 - 1, grid.cols // 2, grid.cols - 1):
+                    with self.subTest(grid=grid.name, row=row, col=col):
+                        self.assertEqual(grid.cell(*grid.center(row, col)), (row, col))
+
+    def test_out_of_range_index_names_valid_ranges(self):
+        for row, col in ((-1, 0), (0, 964), (406, 0)):
+            with self.assertRaisesRegex(ValueError, 'row must be 0-405 and col 0-963'):
+                GRIDS['M36'].center(row, col)
+
+    def test_longitude_180_wraps_to_first_column(self):
+        self.assertEqual(GRIDS['M36'].cell(10, 180)[1], 0)
+
+    def test_outside_latitude_band(self):
+        with self.assertRaises(ValueError):
+            GRIDS['M09'].cell(86, 0)
+        with self.assertRaises(ValueError):
+            GRIDS['M09'].center(1624, 0)
+
+    def test_window_covers_radius(self):
+        m09 = GRIDS['M09']
+        rows, cols = m09.window(39.55, -107.32, 20)
+        row, col = m09.cell(39.55, -107.32)
+        self.assertTrue(rows.start <= row - 2 and row + 2 < rows.stop)
+        # Cells are narrower on the ground away from the equator, so more columns are needed
+        self.assertGreater(cols.stop - cols.start, rows.stop - rows.start)
+        self.assertEqual(m09.window(85.0, 0, 50)[0].start, 0)
+
+
+if __name__ == '__main__':
+    unittest.main()
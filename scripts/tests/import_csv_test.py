@@ -0,0 +1,79 @@
+import unittest
+import sys
+import os
+import sqlite3
+import tempfile
+from unittest import mock
+
+# Add the parent directory to the Python path
+sys.path.append(os.path.dirname(os.path.dirname(os.path.abspath(__file__))))
+
+import import_csv
+from import_csv import ImportAborted, import_rows
+
+
+class TestImportCsv(unittest.TestCase):
+
+    def setUp(self):
+        self.conn = sqlite3.connect(':memory:')
+
+    def tearDown(self):
+        self.conn.close()
+
+    def row(self, **overrides):
+        row = {'date': '2019-06-01', 'lat': '40.5', 'lon': '-105.25', 'moisture': '0.21'}
+        row.update(overrides)
+        return row
+
+    def stored(self):
+        return self.conn.execute('SELECT date, location, smap_value FROM processed_data ORDER BY rowid').fetchall()
+
+    def test_bad_rows_are_reported_not_fatal(self):
+        rows = [self.row(), self.row(date='2019-06-31'), self.row(date='2019-06-02', moisture='-9999'),
+                self.row(date='2019-06-03', lat='nan'), self.row(), self.row(date='2019-06-04')]
+        report = import_rows(self.conn, rows)
+        self.assertEqual(report['inserted'], 2)
+        self.assertEqual(report['rejected'], 4)
+        self.assertEqual([error['line'] for error in report['errors']], [3, 4, 5, 6])
+        self.assertIn('date', report['errors'][0]['error'])
+        self.assertIn('already has a value', report['errors'][3]['error'])
+        self.assertEqual(self.stored(), [('2019-06-01', '40.5,-105.25', 0.21), ('2019-06-04', '40.5,-105.25', 0.21)])
+        # Imported dates can be verified like ingested ones
+        self.assertEqual(self.conn.execute('SELECT COUNT(*) FROM date_checksums').fetchone()[0], 2)
+        self.assertEqual(self.conn.execute("SELECT row_count FROM ingestion_runs WHERE status = 'imported'")
+                         .fetchone(), (2,))
+
+    def test_strict_import_writes_nothing(self):
+        with mock.patch.object(import_csv, 'BATCH_ROWS', 1):
+            with self.assertRaisesRegex(ImportAborted, 'line 4: moisture'):
+                import_rows(self.conn, [self.row(), self.row(date='2019-06-02'), self.row(moisture='x')],
+                            strict=True)
+        self.assertEqual(self.stored(), [])
+
+    def test_batches_commit_as_they_go(self):
+        def rows():
+            yield self.row()
+            yield self.row(date='2019-06-02')
+            raise KeyboardInterrupt
+        with mock.patch.object(import_csv, 'BATCH_ROWS', 1), self.assertRaises(KeyboardInterrupt):
+            import_rows(self.conn, rows())
+        self.assertEqual(len(self.stored()), 2)
+
+    def test_reported_errors_are_capped(self):
+        report = import_rows(self.conn, [self.row(moisture='x')] * (import_csv.MAX_REPORTED_ERRORS + 5))
+        self.assertEqual(report['rejected'], import_csv.MAX_REPORTED_ERRORS + 5)
+        self.assertEqual(len(report['errors']), import_csv.MAX_REPORTED_ERRORS)
+
+    def test_header_and_size_checked_before_reading(self):
+        with tempfile.NamedTemporaryFile('w', suffix='.csv') as f:
+            f.write('date,latitude,lon,moisture\n2019-06-01,40,-105,0.2\n')
+            f.flush()
+            with self.assertRaisesRegex(ValueError, 'missing lat'):
+                import_csv.open_csv(f.name)
+            with mock.patch.object(import_csv, 'MAX_IMPORT_BYTES', 10):
+                with self.assertRaisesRegex(ValueError, 'byte limit'):
+                    import_csv.open_csv(f.name)
+
+
+if __name__ == '__main__':
+    unittest.main()
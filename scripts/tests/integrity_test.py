@@ -0,0 +1,90 @@
+import unittest
+import sys
+import os
+import sqlite3
+from unittest import mock
+
+# Add the parent directory to the Python path
+sys.path.append(os.path.dirname(os.path.dirname(os.path.abspath(__file__))))
+
+import anomaly
+import integrity
+
+
+class TestIntegrity(unittest.TestCase):
+
+    def setUp(self):
+        self.conn = sqlite3.connect(':memory:')
+        self.conn.execute('CREATE TABLE processed_data (date TEXT, location TEXT, smap_value REAL, vegdri_value REAL)')
+        for day in range(1, 6):
+            anomaly.ingest(self.conn, [(f'2024-05-{day:02d}', location, 0.2 + 0.01 * i, None)
+                                       for i, location in enumerate(('A', 'B', 'C'))])
+
+    def tearDown(self):
+        self.conn.close()
+
+    def verify_all(self, **kwargs):
+        return integrity.verify(self.conn, sample_rate=1.0, pause=0, **kwargs)
+
+    def test_ingestion_records_every_published_date(self):
+        recorded = self.conn.execute('SELECT date, row_count, status FROM date_checksums ORDER BY date').fetchall()
+        self.assertEqual(recorded, [(f'2024-05-{day:02d}', 3, 'ok') for day in range(1, 6)])
+        self.assertEqual(self.verify_all(), [])
+
+    def test_checksum_ignores_row_order(self):
+        first = integrity.checksum(self.conn, '2024-05-01')
+        rows = self.conn.execute("SELECT * FROM processed_data WHERE date = '2024-05-01'").fetchall()
+        self.conn.execute("DELETE FROM processed_data WHERE date = '2024-05-01'")
+        self.conn.executemany('INSERT INTO processed_data VALUES (?, ?, ?, ?)', reversed(rows))
+        self.assertEqual(integrity.checksum(self.conn, '2024-05-01'), first)
+
+    def test_changed_and_missing_rows_are_suspect(self):
+        self.conn.execute("UPDATE processed_data SET smap_value = 0.9 WHERE date = '2024-05-02' AND location = 'A'")
+        self.conn.execute("DELETE FROM processed_data WHERE date = '2024-05-04' AND location = 'C'")
+        discrepancies = self.verify_all()
+        self.assertEqual(discrepancies, [{'date': '2024-05-02', 'expected_rows': 3, 'rows': 3},
+                                         {'date': '2024-05-04', 'expected_rows': 3, 'rows': 2}])
+        self.assertEqual(integrity.suspect_dates(self.conn, '2024-05-01', '2024-05-03'), ['2024-05-02'])
+
+        # A fresh ingestion of the date re-records it as the reference
+        integrity.record(self.conn, ['2024-05-02'])
+        self.assertEqual(integrity.suspect_dates(self.conn, '2024-05-01', '2024-05-31'), ['2024-05-04'])
+
+    def test_mismatch_alert_is_queued_with_the_marks(self):
+        self.conn.execute("DELETE FROM processed_data WHERE date = '2024-05-03'")
+        env = {'OPENFLOW_SMTP_HOST': 'smtp.example.com', 'OPENFLOW_SMTP_FROM': 'openflow@example.com',
+               'OPENFLOW_SMTP_TO': 'ops@example.com'}
+        with mock.patch.dict(os.environ, env):
+            self.verify_all()
+        message, = self.conn.execute("SELECT message FROM outbox WHERE kind = 'integrity_mismatch'").fetchone()
+        self.assertIn('2024-05-03: 0 rows, 3 at ingestion', message)
+
+    def test_scope_and_sample(self):
+        self.conn.execute("DELETE FROM processed_data WHERE date = '2024-05-01'")
+        # Only the latest three dates are in scope
+        self.assertEqual(self.verify_all(days=3), [])
+        verified = self.conn.execute('SELECT COUNT(*) FROM date_checksums WHERE verified_at IS NOT NULL')
+        self.assertEqual(verified.fetchone()[0], 3)
+
+        # A small sample still checks at least one date
+        self.conn.execute('UPDATE date_checksums SET verified_at = NULL')
+        integrity.verify(self.conn, sample_rate=0.01, pause=0)
+        verified = self.conn.execute('SELECT COUNT(*) FROM date_checksums WHERE verified_at IS NOT NULL')
+        self.assertEqual(verified.fetchone()[0], 1)
+
+    def test_approved_run_is_recorded(self):
+        run_id, status, _ = anomaly.ingest(self.conn, [('2024-05-09', location, 0.8, None) for location in 'ABC'])
+        self.assertEqual(status, 'quarantined')
+        self.assertIsNone(self.conn.execute("SELECT 1 FROM date_checksums WHERE date = '2024-05-09'").fetchone())
+        anomaly.approve(self.conn, run_id)
+        self.assertEqual(self.conn.execute("SELECT row_count FROM date_checksums WHERE date = '2024-05-09'")
+                         .fetchone(), (3,))
+
+    def test_no_checksums_table(self):
+        conn = sqlite3.connect(':memory:')
+        self.assertEqual(integrity.suspect_dates(conn, '2024-05-01', '2024-05-31'), [])
+        conn.close()
+
+
+if __name__ == '__main__':
+    unittest.main()
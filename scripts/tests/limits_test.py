@@ -0,0 +1,89 @@
+import unittest
+import sys
+import os
+import threading
+
+# Add the parent directory to the Python path
+sys.path.append(os.path.dirname(os.path.dirname(os.path.abspath(__file__))))
+
+from limits import ConcurrencyLimiter, parse_route_limits
+
+
+class TestParseRouteLimits(unittest.TestCase):
+
+    def test_parses_pairs(self):
+        self.assertEqual(parse_route_limits('/export=2, /compare=4,'), {'/export': 2, '/compare': 4})
+        self.assertEqual(parse_route_limits(''), {})
+
+    def test_rejects_malformed(self):
+        for spec in ('export=2', '/export', '/export=0', '/export=x'):
+            with self.assertRaises(ValueError):
+                parse_route_limits(spec)
+
+
+class TestConcurrencyLimiter(unittest.TestCase):
+
+    def slow_request(self, limiter, route, started, finish):
+        """Stand-in for a request stuck on slow storage"""
+        def run():
+            self.assertTrue(limiter.acquire(route))
+            started.set()
+            finish.wait(5)
+            limiter.release(route)
+        thread = threading.Thread(target=run)
+        thread.start()
+        return thread
+
+    def test_saturated_route_leaves_others_available(self):
+        limiter = ConcurrencyLimiter(4, {'/export': 1})
+        started, finish = threading.Event(), threading.Event()
+        thread = self.slow_request(limiter, '/export', started, finish)
+        started.wait(5)
+
+        self.assertFalse(limiter.acquire('/export'))
+        self.assertTrue(limiter.acquire('/data'))
+        self.assertEqual(limiter.snapshot()['*']['in_flight'], 2)
+        limiter.release('/data')
+
+        finish.set()
+        thread.join()
+        self.assertEqual(limiter.snapshot(), {'*': {'in_flight': 0, 'limit': 4},
+                                              '/export': {'in_flight': 0, 'limit': 1}})
+        self.assertTrue(limiter.acquire('/export'))
+
+    def test_global_ceiling(self):
+        limiter = ConcurrencyLimiter(2)
+        self.assertTrue(limiter.acquire('/data'))
+        self.assertTrue(limiter.acquire('/trend'))
+        self.assertFalse(limiter.acquire('/grid'))
+        limiter.release('/trend')
+        self.assertTrue(limiter.acquire('/grid'))
+
+    def test_rejected_route_slot_does_not_consume_global(self):
+        limiter = ConcurrencyLimiter(2, {'/export': 1})
+        self.assertTrue(limiter.acquire('/export'))
+        self.assertFalse(limiter.acquire('/export'))
+        self.assertEqual(limiter.snapshot()['*']['in_flight'], 1)
+
+    def test_held_body_releases_once_on_close(self):
+        limiter = ConcurrencyLimiter(1, {'/export': 1})
+        self.assertTrue(limiter.acquire('/export'))
+        closed = []
+
+        def chunks():
+            try:
+                yield 'a'
+                yield 'b'
+            finally:
+                closed.append(True)
+        body = limiter.hold(chunks(), '/export')
+        self.assertEqual(next(iter(body)), 'a')
+        self.assertFalse(limiter.acquire('/data'))
+        body.close()
+        body.close()
+        self.assertEqual(closed, [True])
+        self.assertEqual(limiter.snapshot()['*']['in_flight'], 0)
+
+
+if __name__ == '__main__':
+    unittest.main()
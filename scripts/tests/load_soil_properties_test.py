@@ -0,0 +1,47 @@
+import unittest
+import sys
+import os
+import sqlite3
+
+# Add the parent directory to the Python path
+sys.path.append(os.path.dirname(os.path.dirname(os.path.abspath(__file__))))
+
+from load_soil_properties import load_soil_properties
+
+
+class TestLoadSoilProperties(unittest.TestCase):
+
+    def setUp(self):
+        self.conn = sqlite3.connect(':memory:')
+
+    def tearDown(self):
+        self.conn.close()
+
+    def row(self, **overrides):
+        row = {'location': 'USGS:09085000', 'sand': '0.6', 'clay': '0.1',
+               'field_capacity': '0.2', 'wilting_point': '0.06'}
+        row.update(overrides)
+        return row
+
+    def test_import_and_replace(self):
+        self.assertEqual(load_soil_properties(self.conn, [self.row()]), 1)
+        load_soil_properties(self.conn, [self.row(field_capacity='0.25')])
+        self.assertEqual(self.conn.execute('SELECT location, field_capacity FROM soil_properties').fetchall(),
+                         [('USGS:09085000', 0.25)])
+
+    def test_invalid_row_names_line_and_writes_nothing(self):
+        rows = [self.row(), self.row(location='DWR:CLAGLECO', wilting_point='0.3')]
+        with self.assertRaisesRegex(ValueError, 'line 3: field_capacity'):
+            load_soil_properties(self.conn, rows)
+        with self.assertRaises(sqlite3.OperationalError):
+            self.conn.execute('SELECT * FROM soil_properties')
+
+    def test_rejects_bad_values(self):
+        for row in (self.row(location=''), self.row(sand='lots'), self.row(clay='1.5'),
+                    self.row(sand='0.7', clay='0.4')):
+            with self.assertRaises(ValueError):
+                load_soil_properties(self.conn, [row])
+
+
+if __name__ == '__main__':
+    unittest.main()
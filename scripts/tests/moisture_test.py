@@ -0,0 +1,25 @@
+import unittest
+import sys
+import os
+import sqlite3
+
+# Add the parent directory to the Python path
+sys.path.append(os.path.dirname(os.path.dirname(os.path.abspath(__file__))))
+
+from moisture import VALID_MOISTURE, is_valid
+
+
+class TestMoisture(unittest.TestCase):
+
+    def test_sql_and_python_checks_agree(self):
+        values = [0.0, 0.25, 1.0, -9999.0, -0.01, 1.01, None, float('nan')]
+        conn = sqlite3.connect(':memory:')
+        conn.execute('CREATE TABLE processed_data (smap_value REAL)')
+        conn.executemany('INSERT INTO processed_data VALUES (?)', [(value,) for value in values])
+        stored = [row[0] for row in conn.execute(f'SELECT smap_value FROM processed_data WHERE {VALID_MOISTURE}')]
+        self.assertEqual(stored, [0.0, 0.25, 1.0])
+        self.assertEqual([value for value in values if is_valid(value)], stored)
+
+
+if __name__ == '__main__':
+    unittest.main()
@@ -0,0 +1,108 @@
+import unittest
+import sys
+import os
+import smtplib
+import sqlite3
+import tempfile
+from unittest import mock
+
+# Add the parent directory to the Python path
+sys.path.append(os.path.dirname(os.path.dirname(os.path.abspath(__file__))))
+
+import notify
+
+SMTP_ENV = {'OPENFLOW_SMTP_HOST': 'smtp.example.com', 'OPENFLOW_SMTP_FROM': 'openflow@example.com',
+            'OPENFLOW_SMTP_TO': 'ops@example.com, farm@example.com'}
+
+
+class TestNotify(unittest.TestCase):
+
+    def test_disabled_without_configuration(self):
+        with mock.patch.dict(os.environ, {}, clear=True), mock.patch.object(notify, '_send') as send:
+            self.assertFalse(notify.notify('test'))
+            send.assert_not_called()
+
+    def test_renders_template_values(self):
+        with mock.patch.dict(os.environ, SMTP_ENV):
+            message = notify.render('ingestion_failed', start_date='2023-01-01', end_date='2023-01-31',
+                                    error='granule search timed out', log_path='/var/log/openflow_cron.log')
+        self.assertEqual(message['Subject'], 'OpenFlow ingestion failed for 2023-01-01 to 2023-01-31')
+        self.assertEqual(message['To'], 'ops@example.com, farm@example.com')
+        self.assertIn('granule search timed out', message.get_content())
+
+    def test_retries_then_gives_up_without_raising(self):
+        with mock.patch.dict(os.environ, SMTP_ENV), \
+                mock.patch.object(notify, '_send', side_effect=smtplib.SMTPException('refused')) as send, \
+                mock.patch.object(notify.time, 'sleep') as sleep:
+            self.assertFalse(notify.notify('test'))
+        self.assertEqual(send.call_count, notify.SEND_ATTEMPTS)
+        self.assertEqual([call.args[0] for call in sleep.call_args_list], [5.0, 10.0])
+
+    def test_succeeds_after_transient_failure(self):
+        with mock.patch.dict(os.environ, SMTP_ENV), \
+                mock.patch.object(notify, '_send', side_effect=[ConnectionRefusedError(), None]), \
+                mock.patch.object(notify.time, 'sleep'):
+            self.assertTrue(notify.notify('test'))
+
+
+class TestOutbox(unittest.TestCase):
+
+    def setUp(self):
+        self.tmp = tempfile.TemporaryDirectory()
+        self.env = dict(SMTP_ENV, OPENFLOW_OUTBOX_DB=os.path.join(self.tmp.name, 'outbox.db'))
+
+    def tearDown(self):
+        self.tmp.cleanup()
+
+    def states(self):
+        with sqlite3.connect(self.env['OPENFLOW_OUTBOX_DB']) as conn:
+            return conn.execute('SELECT state, attempts FROM outbox ORDER BY id').fetchall()
+
+    def make_due(self):
+        with sqlite3.connect(self.env['OPENFLOW_OUTBOX_DB']) as conn:
+            conn.execute('UPDATE outbox SET next_attempt = 0')
+
+    def dispatch(self, **send):
+        with mock.patch.dict(os.environ, self.env), mock.patch.object(notify, '_send', **send) as sender:
+            conn = notify._outbox()
+            try:
+                return notify.dispatch(conn), sender
+            finally:
+                conn.close()
+
+    def test_delivered_message_is_marked_sent(self):
+        with mock.patch.dict(os.environ, self.env), mock.patch.object(notify, '_send'):
+            self.assertTrue(notify.notify('test'))
+        self.assertEqual(self.states(), [('sent', 0)])
+
+    def test_failed_message_is_retried_by_dispatcher(self):
+        with mock.patch.dict(os.environ, self.env), \
+                mock.patch.object(notify, '_send', side_effect=ConnectionRefusedError()), \
+                mock.patch.object(notify.time, 'sleep'):
+            self.assertFalse(notify.notify('test'))
+        self.assertEqual(self.states(), [('pending', 0)])
+
+        # Not due until the first dispatch delay has passed
+        self.assertEqual(self.dispatch()[0], {'sent': 0, 'failed': 0})
+        self.make_due()
+        counts, sender = self.dispatch()
+        self.assertEqual(counts, {'sent': 1, 'failed': 0})
+        self.assertEqual(sender.call_args.args[0]['Subject'], 'OpenFlow test email')
+        self.assertEqual(self.states(), [('sent', 0)])
+
+    def test_dead_letters_after_max_dispatches(self):
+        with mock.patch.dict(os.environ, self.env):
+            conn = notify._outbox()
+            with conn:
+                notify.enqueue(conn, 'ingestion_quarantined', run_id=7, reasons='mean shifted', rows=40)
+            conn.close()
+        for _ in range(notify.MAX_DISPATCHES):
+            self.make_due()
+            self.dispatch(side_effect=smtplib.SMTPException('refused'))
+        self.assertEqual(self.states(), [('dead', notify.MAX_DISPATCHES)])
+        self.make_due()
+        self.assertEqual(self.dispatch()[0], {'sent': 0, 'failed': 0})
+
+
+if __name__ == '__main__':
+    unittest.main()
@@ -0,0 +1,217 @@
+import io
+import json
+import unittest
+import sys
+import os
+import sqlite3
+import tempfile
+from datetime import date, timedelta
+from unittest import mock
+
+# Add the parent directory to the Python path
+sys.path.append(os.path.dirname(os.path.dirname(os.path.abspath(__file__))))
+
+import climatology
+
+try:
+    import openflow_api
+except ImportError:
+    # bottle and waitress are only installed where the API is deployed
+    openflow_api = None
+
+DAY = {'date': '2023-07-06', 'latitude': 40.0, 'elevation': 1500, 'tmin': 10.0, 'tmax': 28.0,
+       'wind_speed': 2.0, 'rh_mean': 45, 'solar_radiation': 25.0}
+
+
+@unittest.skipIf(openflow_api is None, "bottle/waitress not installed")
+class ApiTestCase(unittest.TestCase):
+    """Calls routes through the WSGI app, plugins included"""
+
+    def call(self, method, path, body=None, query=''):
+        raw = json.dumps(body).encode('utf-8') if body is not None else b''
+        environ = {
+            'REQUEST_METHOD': method, 'PATH_INFO': path, 'QUERY_STRING': query,
+            'CONTENT_TYPE': 'application/json', 'CONTENT_LENGTH': str(len(raw)),
+            'SERVER_NAME': 'localhost', 'SERVER_PORT': '80', 'SERVER_PROTOCOL': 'HTTP/1.1',
+            'wsgi.input': io.BytesIO(raw), 'wsgi.errors': io.StringIO(), 'wsgi.url_scheme': 'http',
+            'wsgi.version': (1, 0), 'wsgi.multithread': False, 'wsgi.multiprocess': False,
+            'wsgi.run_once': False
+        }
+        status = []
+        chunks = openflow_api.app(environ, lambda line, headers, exc_info=None: status.append(line))
+        return int(status[0].split()[0]), json.loads(b''.join(chunks))
+
+    def use_rows(self, rows):
+        """Serve processed_data rows of (date, location, smap_value) from a temporary database"""
+        tmp = tempfile.TemporaryDirectory()
+        self.addCleanup(tmp.cleanup)
+        path = os.path.join(tmp.name, 'data.db')
+        with sqlite3.connect(path) as conn:
+            conn.execute('CREATE TABLE processed_data (date TEXT, location TEXT, smap_value REAL, vegdri_value REAL)')
+            conn.executemany('INSERT INTO processed_data VALUES (?, ?, ?, NULL)', rows)
+        conn.close()
+        patcher = mock.patch.object(openflow_api, 'DB_PATH', path)
+        patcher.start()
+        self.addCleanup(patcher.stop)
+
+
+class TestEt0(ApiTestCase):
+
+    def test_valid_day(self):
+        status, body = self.call('POST', '/et0', DAY)
+        self.assertEqual(status, 200)
+        self.assertGreater(body['et0'], 0)
+
+    def test_impossible_conditions_are_bad_requests(self):
+        # Formerly a complex-valued pressure (TypeError) and a division by zero: 500s
+        for override, pointer in (({'elevation': 50000}, '/elevation'),
+                                  ({'tmin': -237.3, 'tmax': -237.3}, '/tmin')):
+            with self.subTest(override=override):
+                status, body = self.call('POST', '/et0', {**DAY, **override})
+                self.assertEqual(status, 400)
+                self.assertEqual(body['pointer'], pointer)
+
+    def test_non_finite_numbers_are_bad_requests(self):
+        # json.dumps writes these as the NaN and Infinity literals json.loads accepts
+        for field, value in (('wind_speed', float('nan')), ('wind_height', float('inf'))):
+            with self.subTest(field=field):
+                status, body = self.call('POST', '/et0', {**DAY, field: value})
+                self.assertEqual(status, 400)
+                self.assertEqual(body['pointer'], f'/{field}')
+
+    def test_batch_failure_points_at_the_day(self):
+        status, body = self.call('POST', '/et0', {'days': [DAY, {**DAY, 'elevation': 50000}]})
+        self.assertEqual(status, 400)
+        self.assertEqual(body['pointer'], '/days/1/elevation')
+
+
+class TestTrend(ApiTestCase):
+
+    def test_fill_values_are_excluded(self):
+        rows = [(f'2024-05-{day:02d}', 'A', 0.30 - 0.01 * day) for day in range(1, 11)]
+        # A fill value on the latest day would otherwise end the window and wreck the slope
+        self.use_rows(rows + [('2024-05-11', 'A', -9999.0)])
+        status, body = self.call('GET', '/trend', query='location=A&window=14')
+        self.assertEqual(status, 200)
+        self.assertEqual(body['end_date'], '2024-05-10')
+        self.assertEqual(body['observations'], 10)
+        self.assertAlmostEqual(body['slope_per_day'], -0.01)
+        self.assertEqual(body['label'], 'drying')
+
+
+class TestData(ApiTestCase):
+
+    def test_gap_fill_skips_fill_values(self):
+        self.use_rows([('2024-05-01', 'A', 0.20), ('2024-05-03', 'A', -9999.0), ('2024-05-05', 'A', 0.30)])
+        query = 'start_date=2024-05-01&end_date=2024-05-05&fill={}'
+        status, body = self.call('GET', '/data', query=query.format('interpolate'))
+        self.assertEqual(status, 200)
+        values = [(row['date'], row['smap_value'], row['filled']) for row in body]
+        self.assertEqual([value for _, value, _ in values], [0.2, 0.225, 0.25, 0.275, 0.3])
+        self.assertEqual(values[2], ('2024-05-03', 0.25, True))
+
+        _, body = self.call('GET', '/data', query=query.format('previous'))
+        self.assertEqual([row['smap_value'] for row in body], [0.2, 0.2, 0.2, 0.2, 0.3])
+
+
+    def test_percentile_against_climatology(self):
+        # Three earlier Mays at A, evenly spread from 0.10 to 0.40; a single earlier day at B
+        history = [(day.isoformat(), 'A', 0.10 + 0.30 * i / 44)
+                   for i, day in enumerate(date(year, 5, 3) + timedelta(days=d)
+                                           for year in (2021, 2022, 2023) for d in range(15))]
+        self.use_rows(history + [('2023-05-10', 'B', 0.2), ('2024-05-10', 'A', 0.25),
+                                 ('2024-05-10', 'B', 0.2), ('2024-05-11', 'A', -9999.0)])
+        with sqlite3.connect(openflow_api.DB_PATH) as conn:
+            climatology.update(conn)
+        conn.close()
+        status, body = self.call('GET', '/data', query='start_date=2024-05-10&end_date=2024-05-11&percentile=true')
+        self.assertEqual(status, 200)
+        rows = {(row['date'], row['location']): row for row in body}
+        self.assertAlmostEqual(rows['2024-05-10', 'A']['percentile'], 50, delta=5)
+        self.assertNotIn('percentile_reason', rows['2024-05-10', 'A'])
+        self.assertIsNone(rows['2024-05-10', 'B']['percentile'])
+        self.assertIn('historical observations', rows['2024-05-10', 'B']['percentile_reason'])
+        self.assertEqual(rows['2024-05-11', 'A']['percentile_reason'], 'no valid soil moisture value')
+
+
+class TestPlantAvailableWater(ApiTestCase):
+
+    def test_fill_value_is_missing_not_dry(self):
+        self.use_rows([('2024-05-01', 'A', 0.20), ('2024-05-02', 'A', -9999.0)])
+        with sqlite3.connect(openflow_api.DB_PATH) as conn:
+            conn.execute('CREATE TABLE soil_properties (location TEXT, sand REAL, clay REAL, '
+                         'field_capacity REAL, wilting_point REAL)')
+            conn.execute("INSERT INTO soil_properties VALUES ('A', 0.6, 0.1, 0.30, 0.10)")
+        conn.close()
+        status, body = self.call('GET', '/data', query='start_date=2024-05-01&end_date=2024-05-02&paw=true')
+        self.assertEqual(status, 200)
+        paw = {row['date']: (row['paw'], row.get('paw_reason')) for row in body}
+        self.assertEqual(paw, {'2024-05-01': (0.5, None), '2024-05-02': (None, 'soil moisture is a fill value')})
+
+
+    def test_bad_end_date_is_rejected_before_connecting(self):
+        with mock.patch.object(openflow_api, 'connect') as connect:
+            status, _ = self.call('GET', '/trend', query='location=A&end_date=2024-13-01')
+        self.assertEqual(status, 400)
+        connect.assert_not_called()
+
+
+class TestCompare(ApiTestCase):
+
+    def test_duplicate_rows_pair_once(self):
+        rows = [(f'2024-05-0{day}', location, value) for day in range(1, 5)
+                for location, value in (('A', 0.20 + 0.02 * day), ('B', 0.10 + 0.01 * day))
+                if (day, location) != (4, 'B')]
+        # A second copy of every A row, one differing, and only a fill value at B on the 4th
+        self.use_rows(rows + [(day, 'A', value) for day, location, value in rows if location == 'A']
+                      + [('2024-05-01', 'A', 0.30), ('2024-05-04', 'B', -9999.0)])
+        status, body = self.call('GET', '/compare',
+                                 query='location_a=A&location_b=B&start_date=2024-05-01&end_date=2024-05-04')
+        self.assertEqual(status, 200)
+        self.assertEqual(body['summary']['paired_days'], 3)
+        self.assertEqual([row['date'] for row in body['data']], ['2024-05-01', '2024-05-02', '2024-05-03'])
+        # 2024-05-01 at A averages 0.22, 0.22 and 0.30
+        self.assertAlmostEqual(body['data'][0]['value_a'], 0.74 / 3)
+
+
+class TestDrought(ApiTestCase):
+
+    def test_bad_date_is_rejected_before_connecting(self):
+        with mock.patch.object(openflow_api, 'connect') as connect:
+            status, _ = self.call('GET', '/drought', query='location=A&date=yesterday')
+        self.assertEqual(status, 400)
+        connect.assert_not_called()
+
+
+class TestWaterBalance(ApiTestCase):
+
+    def test_series_are_aligned(self):
+        self.use_rows([('2024-05-01', 'A', 0.20), ('2024-05-03', 'A', 0.30), ('2024-05-02', 'A', -9999.0)])
+        with sqlite3.connect(openflow_api.DB_PATH) as conn:
+            conn.execute('CREATE TABLE precipitation (date TEXT, location TEXT, precipitation REAL)')
+            conn.executemany('INSERT INTO precipitation VALUES (?, ?, ?)',
+                             [('2024-05-02', 'A', 12.0), ('2024-05-03', 'A', 0.0), ('2024-05-02', 'B', 50.0)])
+        conn.close()
+        status, body = self.call('GET', '/water_balance',
+                                 query='location=A&start_date=2024-05-01&end_date=2024-05-04')
+        self.assertEqual(status, 200)
+        self.assertEqual(body['precipitation_days'], 2)
+        self.assertEqual([(day['date'], day['moisture'], day['precipitation'], day['balance'])
+                          for day in body['data']],
+                         [('2024-05-01', 0.2, None, 0.0), ('2024-05-02', None, 12.0, None),
+                          ('2024-05-03', 0.3, 0.0, 7.0), ('2024-05-04', None, None, None)])
+
+    def test_no_precipitation_fetched(self):
+        self.use_rows([('2024-05-01', 'A', 0.20)])
+        status, body = self.call('GET', '/water_balance',
+                                 query='location=A&start_date=2024-05-01&end_date=2024-05-01')
+        self.assertEqual(status, 200)
+        self.assertEqual(body['precipitation_days'], 0)
+
+    def test_range_is_limited(self):
+        status, _ = self.call('GET', '/water_balance', query='location=A&start_date=2023-01-01&end_date=2024-05-01')
+        self.assertEqual(status, 400)
+
+
+if __name__ == '__main__':
+    unittest.main()
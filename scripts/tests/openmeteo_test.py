@@ -0,0 +1,68 @@
+import unittest
+import sys
+import os
+import sqlite3
+from datetime import datetime
+from unittest import mock
+
+# Add the parent directory to the Python path
+sys.path.append(os.path.dirname(os.path.dirname(os.path.abspath(__file__))))
+
+import openmeteo
+
+
+class TestPrecipitationStore(unittest.TestCase):
+
+    def setUp(self):
+        self.conn = sqlite3.connect(':memory:')
+
+    def tearDown(self):
+        self.conn.close()
+
+    def stored(self):
+        return self.conn.execute('SELECT date, location, precipitation FROM precipitation '
+                                 'ORDER BY location, date').fetchall()
+
+    def test_ad_hoc_fetch_needs_no_registration(self):
+        totals = {'2024-05-01': 0.0, '2024-05-02': 7.5}
+        with mock.patch.object(openmeteo, 'fetch_daily_precipitation', return_value=totals) as fetch:
+            count = openmeteo.fetch_location(self.conn, 'Field 7', 40.0, -105.5,
+                                             datetime(2024, 5, 1), datetime(2024, 5, 2))
+        self.assertEqual(count, 2)
+        fetch.assert_called_once_with(40.0, -105.5, datetime(2024, 5, 1), datetime(2024, 5, 2))
+        self.assertEqual(self.stored(), [('2024-05-01', 'Field 7', 0.0), ('2024-05-02', 'Field 7', 7.5)])
+
+    def test_update_fetches_registered_locations(self):
+        openmeteo.register(self.conn, 'A', 40.0, -105.0)
+        openmeteo.register(self.conn, 'B', 39.0, -104.0)
+        with mock.patch.object(openmeteo, 'fetch_daily_precipitation',
+                               side_effect=[{'2024-05-01': 1.0}, None]) as fetch, \
+                mock.patch.object(openmeteo.time, 'sleep') as sleep:
+            self.assertEqual(openmeteo.update(self.conn, days=3), 1)
+        self.assertEqual([call.args[:2] for call in fetch.call_args_list], [(40.0, -105.0), (39.0, -104.0)])
+        start, end = fetch.call_args.args[2:]
+        self.assertEqual((end - start).days, 2)
+        # Requests are spaced out for Open-Meteo's rate limit
+        sleep.assert_called_once_with(openmeteo.PrecipitationProcessor.REQUEST_DELAY)
+        # A failed location is skipped rather than failing the run
+        self.assertEqual(self.stored(), [('2024-05-01', 'A', 1.0)])
+
+
+class TestFetch(unittest.TestCase):
+
+    def fetch(self, reply):
+        session = mock.Mock(get=mock.Mock(return_value=reply))
+        with mock.patch.object(openmeteo.http_client, 'session', return_value=session):
+            return openmeteo.fetch_daily_precipitation(40.0, -105.5, datetime(2024, 5, 1), datetime(2024, 5, 2))
+
+    def test_rate_limit_returns_nothing(self):
+        self.assertIsNone(self.fetch(mock.Mock(status_code=429)))
+
+    def test_missing_totals_are_dropped(self):
+        daily = {'time': ['2024-05-01', '2024-05-02'], 'precipitation_sum': [2.5, None]}
+        reply = mock.Mock(status_code=200, json=lambda: {'daily': daily})
+        self.assertEqual(self.fetch(reply), {'2024-05-01': 2.5})
+
+
+if __name__ == '__main__':
+    unittest.main()
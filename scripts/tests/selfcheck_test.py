@@ -0,0 +1,76 @@
+import unittest
+import sys
+import os
+import sqlite3
+import tempfile
+from pathlib import Path
+from unittest.mock import MagicMock, patch
+
+# Add the parent directory to the Python path
+sys.path.append(os.path.dirname(os.path.dirname(os.path.abspath(__file__))))
+
+import selfcheck
+
+
+class TestSelfCheck(unittest.TestCase):
+
+    def setUp(self):
+        self.tmp = tempfile.TemporaryDirectory()
+        self.db_path = os.path.join(self.tmp.name, 'data.db')
+
+    def tearDown(self):
+        self.tmp.cleanup()
+
+    def run_offline(self, **kwargs):
+        with patch.object(selfcheck, 'check_hdf5', return_value='stub'), \
+             patch.object(selfcheck.tempdirs, 'TEMP_ROOT', Path(self.tmp.name) / 'scratch'):
+            return {item['check']: item for item in selfcheck.run_checks(network=False, **kwargs)}
+
+    def test_api_schema_passes(self):
+        conn = sqlite3.connect(self.db_path)
+        conn.execute("CREATE TABLE processed_data (date TEXT, location TEXT, smap_value REAL, vegdri_value REAL)")
+        conn.close()
+
+        report = self.run_offline(api_db=self.db_path)
+        self.assertTrue(all(item['ok'] for item in report.values()))
+        self.assertTrue((Path(self.tmp.name) / 'scratch').is_dir())
+
+    def test_api_schema_reports_missing_columns(self):
+        conn = sqlite3.connect(self.db_path)
+        conn.execute("CREATE TABLE processed_data (date TEXT, location TEXT)")
+        conn.close()
+
+        report = self.run_offline(api_db=self.db_path)
+        self.assertFalse(report['api_schema']['ok'])
+        self.assertIn('smap_value', report['api_schema']['detail'])
+        self.assertTrue(report['temp_dir']['ok'])
+
+    def test_missing_database_fails_without_creating_it(self):
+        report = self.run_offline(api_db=self.db_path)
+        self.assertFalse(report['api_schema']['ok'])
+        self.assertFalse(os.path.exists(self.db_path))
+
+    def test_endpoint_status(self):
+        session = MagicMock()
+        session.head.return_value.status_code = 405
+        with patch.object(selfcheck.http_client, 'session', return_value=session):
+            self.assertEqual(selfcheck.check_endpoint('https://example.org'), 'HTTP 405')
+            session.head.return_value.status_code = 503
+            with self.assertRaises(RuntimeError):
+                selfcheck.check_endpoint('https://example.org')
+
+    def test_earthdata_requires_credentials(self):
+        with patch.dict(os.environ, {}, clear=True):
+            with self.assertRaises(RuntimeError):
+                selfcheck.check_earthdata()
+
+    def test_main_exit_code(self):
+        with patch.object(selfcheck, 'run_checks', return_value=[{'check': 'x', 'ok': False, 'detail': ''}]), \
+             patch('builtins.print'):
+            with self.assertRaises(SystemExit) as raised:
+                selfcheck.main(['--offline'])
+        self.assertEqual(raised.exception.code, 1)
+
+
+if __name__ == '__main__':
+    unittest.main()
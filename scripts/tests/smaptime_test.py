@@ -0,0 +1,34 @@
+import unittest
+import sys
+import os
+from datetime import datetime, timezone
+
+# Add the parent directory to the Python path
+sys.path.append(os.path.dirname(os.path.dirname(os.path.abspath(__file__))))
+
+import smaptime
+
+
+class TestSmapTime(unittest.TestCase):
+
+    def test_epoch_and_offsets(self):
+        self.assertEqual(smaptime.rfc3339(smaptime.from_j2000(1)), '2000-01-01T11:58:56Z')
+        # 2015-03-31 is 5568 days after the epoch's calendar day
+        moment = smaptime.from_j2000(5568 * 86400)
+        self.assertEqual(smaptime.rfc3339(moment), '2015-03-31T11:58:55Z')
+        self.assertEqual(moment.tzinfo, timezone.utc)
+
+    def test_fill_values(self):
+        for value in (None, smaptime.FILL_VALUE, float('nan'), 0):
+            self.assertIsNone(smaptime.from_j2000(value))
+        self.assertIsNone(smaptime.rfc3339(None))
+
+    def test_nominal_overpass(self):
+        day = datetime(2024, 7, 1)
+        # 6am local solar time at 105 W is 13:00 UTC
+        self.assertEqual(smaptime.rfc3339(smaptime.nominal_overpass(day, -105.0, True)), '2024-07-01T13:00:00Z')
+        self.assertEqual(smaptime.rfc3339(smaptime.nominal_overpass(day, 0.0, False)), '2024-07-01T18:00:00Z')
+
+
+if __name__ == '__main__':
+    unittest.main()
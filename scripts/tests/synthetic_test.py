@@ -0,0 +1,104 @@
+import unittest
+import sys
+import os
+import sqlite3
+import tempfile
+from datetime import datetime
+from pathlib import Path
+from unittest.mock import patch
+
+# Add the parent directory to the Python path
+sys.path.append(os.path.dirname(os.path.dirname(os.path.abspath(__file__))))
+
+from init_dbs import setup_database
+import smapprocessor
+from smapprocessor import Hdf5Unavailable, SMAPProcessor
+from stations import Station
+
+
+class TestSyntheticMode(unittest.TestCase):
+
+    def setUp(self):
+        # SMAPProcessor writes to data/earth_data.db relative to the working directory
+        self.cwd = os.getcwd()
+        self.tmp = tempfile.TemporaryDirectory()
+        os.chdir(self.tmp.name)
+        Path("data").mkdir()
+        self.db_path = Path("data/earth_data.db")
+        setup_database(self.db_path)
+        self.stations = [Station(id="USGS:09085000", latitude=39.55, longitude=-107.32),
+                         Station(id="DWR:CLAGLECO", latitude=40.0, longitude=-105.5)]
+
+    def tearDown(self):
+        os.chdir(self.cwd)
+        self.tmp.cleanup()
+
+    def rows(self):
+        with sqlite3.connect(self.db_path) as conn:
+            return conn.execute('''
+                SELECT timestamp, station_id, soil_moisture, quality_flag, synthetic, source
+                FROM smap_features ORDER BY timestamp, station_id
+            ''').fetchall()
+
+    def run_synthetic(self, seed=0):
+        SMAPProcessor(self.stations, datetime(2023, 4, 1), datetime(2023, 4, 10),
+                      synthetic=True, seed=seed)
+        return self.rows()
+
+    def test_fills_every_station_and_day(self):
+        rows = self.run_synthetic()
+        self.assertEqual(len(rows), 20)
+        self.assertTrue(all(0 < row[2] < 1 for row in rows))
+
+    def test_observation_time_is_am_overpass(self):
+        self.run_synthetic()
+        with sqlite3.connect(self.db_path) as conn:
+            times = conn.execute("SELECT observation_time FROM smap_features WHERE station_id = 'DWR:CLAGLECO' "
+                                 "ORDER BY timestamp").fetchall()
+        # 6am local solar time at 105.5 W
+        self.assertEqual(times[0][0], '2023-04-01T13:02:00Z')
+
+    def test_rows_are_tagged_synthetic(self):
+        rows = self.run_synthetic()
+        self.assertEqual({row[4] for row in rows}, {1})
+        # source keeps its L3/L4 product meaning and isn't used for provenance
+        self.assertEqual({row[5] for row in rows}, {None})
+
+    def checksums(self):
+        with sqlite3.connect(self.db_path) as conn:
+            tables = [row[0] for row in conn.execute("SELECT name FROM sqlite_master WHERE type = 'table'")]
+            return {table: conn.execute(f"SELECT * FROM {table} ORDER BY 1, 2").fetchall() for table in tables}
+
+    def test_dry_run_writes_nothing(self):
+        SMAPProcessor(self.stations, datetime(2023, 4, 1), datetime(2023, 4, 5), synthetic=True)
+        before = self.checksums()
+        processor = SMAPProcessor(self.stations, datetime(2023, 4, 1), datetime(2023, 4, 10),
+                                  synthetic=True, seed=3, dry_run=True)
+        self.assertEqual(self.checksums(), before)
+        report = processor.dry_run_report
+        self.assertEqual((report['days'], report['rows'], report['replacements']), (10, 20, 10))
+
+    def test_dry_run_without_database(self):
+        self.db_path.unlink()
+        processor = SMAPProcessor(self.stations, datetime(2023, 4, 1), datetime(2023, 4, 2),
+                                  synthetic=True, dry_run=True)
+        self.assertEqual(processor.dry_run_report['replacements'], 0)
+        self.assertFalse(self.db_path.exists())
+
+    def test_without_hdf5(self):
+        with patch.object(smapprocessor, 'h5py', None), \
+             patch.object(smapprocessor.earthaccess, 'login') as login:
+            with self.assertRaises(Hdf5Unavailable):
+                SMAPProcessor(self.stations, datetime(2023, 4, 1), datetime(2023, 4, 2))
+            login.assert_not_called()
+            # Synthetic data never opens a granule, so it keeps working
+            self.assertEqual(len(self.run_synthetic()), 20)
+
+    def test_deterministic_from_seed(self):
+        first = self.run_synthetic(seed=7)
+        self.assertEqual(self.run_synthetic(seed=7), first)
+        self.assertNotEqual(self.run_synthetic(seed=8), first)
+
+
+if __name__ == '__main__':
+    unittest.main()
@@ -0,0 +1,80 @@
+import unittest
+import sys
+import os
+import tempfile
+import time
+from pathlib import Path
+
+# Add the parent directory to the Python path
+sys.path.append(os.path.dirname(os.path.dirname(os.path.abspath(__file__))))
+
+import tempdirs
+
+
+class TestSweep(unittest.TestCase):
+
+    def setUp(self):
+        self.tmp = tempfile.TemporaryDirectory()
+        self.root = Path(self.tmp.name) / 'scratch'
+        self.root.mkdir()
+        self.now = time.time()
+
+    def tearDown(self):
+        self.tmp.cleanup()
+
+    def make(self, path, age_hours):
+        path.parent.mkdir(parents=True, exist_ok=True)
+        path.write_bytes(b'x' * 10)
+        stamp = self.now - age_hours * 3600
+        os.utime(path, (stamp, stamp))
+        os.utime(path.parent, (stamp, stamp))
+        return path
+
+    def test_removes_only_stale_prefixed_entries(self):
+        stale = self.make(self.root / 'openflow-smap-1-1' / 'granule.h5', 48)
+        fresh = self.make(self.root / 'openflow-smap-2-2' / 'granule.h5', 1)
+        foreign = self.make(self.root / 'someone-elses.h5', 48)
+
+        self.assertEqual(tempdirs.sweep(self.root, self.now, stale_after=24 * 3600), 1)
+        self.assertFalse(stale.parent.exists())
+        self.assertTrue(fresh.exists())
+        self.assertTrue(foreign.exists())
+
+    def test_recent_file_keeps_old_directory(self):
+        self.make(self.root / 'openflow-smap-1-1' / 'old.h5', 48)
+        recent = self.make(self.root / 'openflow-smap-1-1' / 'new.h5', 1)
+        os.utime(recent.parent, (self.now - 48 * 3600,) * 2)
+
+        self.assertEqual(tempdirs.sweep(self.root, self.now, stale_after=24 * 3600), 0)
+        self.assertTrue(recent.exists())
+
+    def test_does_not_follow_symlinks_out_of_root(self):
+        outside = self.make(Path(self.tmp.name) / 'outside' / 'keep.h5', 48)
+        link = self.root / 'openflow-link'
+        link.symlink_to(outside.parent)
+        os.utime(link, (self.now - 48 * 3600,) * 2, follow_symlinks=False)
+
+        self.assertEqual(tempdirs.sweep(self.root, self.now, stale_after=24 * 3600), 1)
+        self.assertFalse(link.exists() or link.is_symlink())
+        self.assertTrue(outside.exists())
+
+    def test_missing_root(self):
+        self.assertEqual(tempdirs.sweep(self.root / 'missing', self.now), 0)
+
+    def test_usage_bytes(self):
+        self.make(self.root / 'openflow-smap-1-1' / 'a.h5', 0)
+        self.make(self.root / 'openflow-smap-1-1' / 'b.h5', 0)
+        self.assertEqual(tempdirs.usage_bytes(self.root), 20)
+
+
+class TestFreeSpace(unittest.TestCase):
+
+    def test_reports_required_and_available(self):
+        with tempfile.TemporaryDirectory() as tmp:
+            with self.assertRaisesRegex(OSError, r'\d+ bytes required, \d+ available'):
+                tempdirs.ensure_free_space(Path(tmp), required=2 ** 62)
+            tempdirs.ensure_free_space(Path(tmp), required=1)
+
+
+if __name__ == '__main__':
+    unittest.main()
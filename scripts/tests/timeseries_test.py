@@ -0,0 +1,194 @@
+import math
+import unittest
+import sys
+import os
+from datetime import date
+
+# Add the parent directory to the Python path
+sys.path.append(os.path.dirname(os.path.dirname(os.path.abspath(__file__))))
+
+from timeseries import correlation, drydown_forecast, fill_gaps, last_recharge, linear_trend, water_balance
+
+
+class TestFillGaps(unittest.TestCase):
+
+    def setUp(self):
+        self.start = date(2023, 1, 1)
+        self.end = date(2023, 1, 7)
+        # Observations on the 2nd, 3rd and 6th leave a leading, inner and trailing gap
+        self.observations = {
+            date(2023, 1, 2): 0.20,
+            date(2023, 1, 3): 0.26,
+            date(2023, 1, 6): 0.32
+        }
+
+    def values(self, series):
+        return [point['value'] for point in series]
+
+    def test_none_leaves_gaps(self):
+        series = fill_gaps(self.observations, self.start, self.end, 'none')
+        self.assertEqual(len(series), 7)
+        self.assertEqual(self.values(series), [None, 0.20, 0.26, None, None, 0.32, None])
+        self.assertFalse(any(point['filled'] for point in series))
+
+    def test_interpolate_inner_gap(self):
+        series = fill_gaps(self.observations, self.start, self.end, 'interpolate')
+        self.assertAlmostEqual(series[3]['value'], 0.28)
+        self.assertAlmostEqual(series[4]['value'], 0.30)
+        self.assertTrue(series[3]['filled'] and series[4]['filled'])
+
+    def test_interpolate_leaves_leading_and_trailing_gaps(self):
+        series = fill_gaps(self.observations, self.start, self.end, 'interpolate')
+        self.assertIsNone(series[0]['value'])
+        self.assertIsNone(series[6]['value'])
+        self.assertFalse(series[0]['filled'] or series[6]['filled'])
+
+    def test_previous_carries_forward_including_trailing_gap(self):
+        series = fill_gaps(self.observations, self.start, self.end, 'previous')
+        self.assertEqual(self.values(series), [None, 0.20, 0.26, 0.26, 0.26, 0.32, 0.32])
+        self.assertEqual([point['filled'] for point in series],
+                         [False, False, False, True, True, False, True])
+
+    def test_gap_longer_than_max_stays_null(self):
+        series = fill_gaps(self.observations, self.start, self.end, 'interpolate', max_gap=1)
+        self.assertIsNone(series[3]['value'])
+        self.assertIsNone(series[4]['value'])
+
+    def test_empty_series(self):
+        for method in ('none', 'interpolate', 'previous'):
+            series = fill_gaps({}, self.start, self.end, method)
+            self.assertEqual(len(series), 7)
+            self.assertTrue(all(point['value'] is None and not point['filled'] for point in series))
+
+    def test_unknown_method(self):
+        with self.assertRaises(ValueError):
+            fill_gaps(self.observations, self.start, self.end, 'spline')
+
+
+class TestLinearTrend(unittest.TestCase):
+
+    def series(self, values):
+        return {date(2023, 6, 1 + i): value for i, value in enumerate(values) if value is not None}
+
+    def test_drying_series(self):
+        trend = linear_trend(self.series([0.30, 0.28, 0.26, 0.24, 0.22]))
+        self.assertAlmostEqual(trend['slope'], -0.02)
+        self.assertAlmostEqual(trend['r_squared'], 1.0)
+        self.assertEqual(trend['observations'], 5)
+
+    def test_wetting_series_with_gaps(self):
+        # Missing days must not distort the per-day slope
+        trend = linear_trend(self.series([0.10, None, 0.16, None, None, 0.25]))
+        self.assertAlmostEqual(trend['slope'], 0.03)
+
+    def test_flat_series(self):
+        trend = linear_trend(self.series([0.2, 0.2, 0.2, 0.2]))
+        self.assertAlmostEqual(trend['slope'], 0.0)
+        self.assertEqual(trend['r_squared'], 1.0)
+
+    def test_noisy_series_has_lower_r_squared(self):
+        trend = linear_trend(self.series([0.20, 0.25, 0.19, 0.26, 0.21]))
+        self.assertLess(trend['r_squared'], 0.5)
+
+    def test_insufficient_observations(self):
+        self.assertIsNone(linear_trend(self.series([0.2, 0.3])))
+        self.assertIsNone(linear_trend({}))
+
+
+class TestCorrelation(unittest.TestCase):
+
+    def test_perfect_and_inverse(self):
+        self.assertAlmostEqual(correlation([1, 2, 3], [2, 4, 6]), 1.0)
+        self.assertAlmostEqual(correlation([1, 2, 3], [6, 4, 2]), -1.0)
+
+    def test_undefined_for_constant_or_short_series(self):
+        self.assertIsNone(correlation([0.2, 0.2, 0.2], [0.1, 0.2, 0.3]))
+        self.assertIsNone(correlation([0.2], [0.1]))
+
+
+class TestDrydownForecast(unittest.TestCase):
+
+    def series(self, values):
+        return {date(2023, 6, 1 + i): value for i, value in enumerate(values)}
+
+    def test_recovers_exponential_drydown(self):
+        # 0.1 asymptote with 10% of the excess lost per day
+        history = self.series([0.1 + 0.2 * 0.9 ** day for day in range(20)])
+        model = drydown_forecast(history, 3)
+        self.assertAlmostEqual(model['asymptote'], 0.1, places=2)
+        self.assertAlmostEqual(model['decay_rate'], -math.log(0.9), places=2)
+        values = [point['value'] for point in model['forecast']]
+        self.assertEqual(values, sorted(values, reverse=True))
+        self.assertEqual(model['forecast'][0]['date'], date(2023, 6, 21))
+
+    def test_uncertainty_widens_with_lead_time(self):
+        history = self.series([0.3, 0.28, 0.27, 0.27, 0.25, 0.24, 0.24, 0.23, 0.21, 0.21])
+        forecast = drydown_forecast(history, 7)['forecast']
+        widths = [point['upper'] - point['lower'] for point in forecast]
+        self.assertEqual(widths, sorted(widths))
+        self.assertGreater(widths[-1], widths[0])
+
+    def test_wetting_history_persists_last_value(self):
+        model = drydown_forecast(self.series([0.1 + 0.01 * day for day in range(12)]), 2)
+        self.assertEqual(model['decay_rate'], 0.0)
+        self.assertAlmostEqual(model['forecast'][1]['value'], 0.21)
+
+    def test_refuses_sparse_history(self):
+        self.assertIsNone(drydown_forecast(self.series([0.3, 0.25, 0.2]), 3))
+
+
+class TestLastRecharge(unittest.TestCase):
+
+    def series(self, values):
+        return {date(2023, 6, 1 + i): value for i, value in enumerate(values) if value is not None}
+
+    def test_finds_most_recent_rise(self):
+        history = self.series([0.20, 0.30, 0.28, 0.26, 0.35, 0.33, 0.31])
+        recharge = last_recharge(history, 0.05)
+        self.assertEqual(recharge['date'], date(2023, 6, 5))
+        self.assertAlmostEqual(recharge['increase'], 0.09)
+
+    def test_rise_spread_over_two_days(self):
+        # Neither daily step exceeds the threshold, but the 48-hour rise does
+        history = self.series([0.20, 0.23, 0.26, 0.25])
+        self.assertEqual(last_recharge(history, 0.05)['date'], date(2023, 6, 3))
+        self.assertIsNone(last_recharge(history, 0.05, max_lag=1))
+
+    def test_rise_across_missing_days(self):
+        history = self.series([0.20, None, 0.28, None, None, 0.40])
+        # Three days between the last two observations is too far apart to count
+        self.assertEqual(last_recharge(history, 0.05)['date'], date(2023, 6, 3))
+
+    def test_no_recharge(self):
+        self.assertIsNone(last_recharge(self.series([0.30, 0.29, 0.28, 0.30, 0.27]), 0.03))
+        self.assertIsNone(last_recharge({}, 0.03))
+        self.assertIsNone(last_recharge(self.series([0.1]), 0.03))
+
+
+class TestWaterBalance(unittest.TestCase):
+
+    def setUp(self):
+        self.start, self.end = date(2023, 6, 1), date(2023, 6, 5)
+
+    def test_rain_not_stored_in_the_layer_is_balance(self):
+        moisture = {date(2023, 6, 1): 0.20, date(2023, 6, 3): 0.30, date(2023, 6, 5): 0.28}
+        precipitation = {date(2023, 6, 1): 4.0, date(2023, 6, 2): 10.0, date(2023, 6, 3): 2.0}
+        series = water_balance(moisture, precipitation, self.start, self.end, depth_mm=50)
+        self.assertEqual([row['date'] for row in series], [date(2023, 6, day) for day in range(1, 6)])
+        # Rain on the first day is already in its observation
+        self.assertEqual(series[0]['balance'], 0.0)
+        self.assertIsNone(series[1]['balance'])
+        # 12 mm fell and 5 mm (0.1 of a 50 mm layer) was stored
+        self.assertAlmostEqual(series[2]['balance'], 7.0)
+        # The layer then lost 1 mm with no rain
+        self.assertAlmostEqual(series[4]['balance'], 8.0)
+        self.assertIsNone(series[4]['precipitation'])
+
+    def test_no_moisture_has_no_balance(self):
+        series = water_balance({}, {date(2023, 6, 2): 3.0}, self.start, self.end, depth_mm=50)
+        self.assertEqual([row['balance'] for row in series], [None] * 5)
+        self.assertEqual(series[1]['precipitation'], 3.0)
+
+
+if __name__ == '__main__':
+    unittest.main()
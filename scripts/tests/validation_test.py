@@ -0,0 +1,84 @@
+import unittest
+import sys
+import os
+
+# Add the parent directory to the Python path
+sys.path.append(os.path.dirname(os.path.dirname(os.path.abspath(__file__))))
+
+import validation
+from validation import InvalidInput, pointer
+
+
+class TestPointer(unittest.TestCase):
+
+    def test_whole_document(self):
+        self.assertEqual(pointer(), '')
+
+    def test_nested_keys_and_indexes(self):
+        self.assertEqual(pointer('days', 3, 'tmax'), '/days/3/tmax')
+        self.assertEqual(pointer('points', 0, 'coords', 12, 1), '/points/0/coords/12/1')
+
+    def test_escapes_reserved_characters(self):
+        self.assertEqual(pointer('a/b', 'm~n'), '/a~1b/m~0n')
+
+
+class TestExpect(unittest.TestCase):
+
+    def test_returns_matching_value(self):
+        self.assertEqual(validation.expect([1], 'array', pointer('days')), [1])
+        self.assertEqual(validation.expect(2.5, 'number', pointer('tmin')), 2.5)
+
+    def test_booleans_are_not_numbers(self):
+        with self.assertRaises(InvalidInput) as caught:
+            validation.expect(True, 'number', pointer('days', 0, 'tmin'))
+        self.assertEqual(caught.exception.to_dict(), {
+            'error': 'expected number, got boolean', 'pointer': '/days/0/tmin',
+            'expected': 'number', 'received': 'true'})
+
+    def test_deeply_nested_failure_reports_full_path(self):
+        body = {'days': [{'tmax': 30}, {'tmax': 31}, {'tmax': 29}, {'tmax': {'value': '31'}}]}
+        at = pointer('days', 3, 'tmax')
+        with self.assertRaises(InvalidInput) as caught:
+            validation.expect(body['days'][3]['tmax'], 'number', at)
+        details = caught.exception.to_dict()
+        self.assertEqual(details['pointer'], '/days/3/tmax')
+        self.assertEqual(details['received'], '{"value":"31"}')
+
+    def test_array_received_snippet_is_truncated(self):
+        with self.assertRaises(InvalidInput) as caught:
+            validation.expect(list(range(100)), 'object', pointer('days', 0))
+        received = caught.exception.to_dict()['received']
+        self.assertEqual(len(received), validation.SNIPPET_LENGTH)
+        self.assertTrue(received.startswith('[0,1,2,') and received.endswith('...'))
+
+    def test_non_finite_numbers_are_rejected(self):
+        body = validation.decode(b'{"wind_speed": NaN, "wind_height": Infinity, "tmax": 1e999}')
+        for field in body:
+            with self.subTest(field=field), self.assertRaises(InvalidInput) as caught:
+                validation.expect(body[field], 'number', pointer(field))
+            self.assertEqual(caught.exception.pointer, f'/{field}')
+
+    def test_received_null_is_reported(self):
+        details = InvalidInput("expected object, got null", '', 'object', None).to_dict()
+        self.assertEqual(details['received'], 'null')
+        self.assertNotIn('received', InvalidInput("too many days", '/days').to_dict())
+
+
+class TestDecode(unittest.TestCase):
+
+    def test_valid_body(self):
+        self.assertEqual(validation.decode(b'{"days": [{"tmin": 1}]}'), {'days': [{'tmin': 1}]})
+
+    def test_syntax_error_locates_line_and_column(self):
+        with self.assertRaises(InvalidInput) as caught:
+            validation.decode(b'{"days": [\n  {"tmin": 1,}\n]}')
+        self.assertIn('line 2 column', str(caught.exception))
+        self.assertEqual(caught.exception.pointer, '')
+
+    def test_rejects_invalid_utf8(self):
+        with self.assertRaisesRegex(InvalidInput, 'UTF-8'):
+            validation.decode(b'{"a": "\xff"}')
+
+
+if __name__ == '__main__':
+    unittest.main()
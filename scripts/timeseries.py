@@ -0,0 +1,226 @@
+import math
+from datetime import date, timedelta
+from typing import Dict, List, Optional
+
+FILL_METHODS = ('none', 'interpolate', 'previous')
+
+
+def daily_range(start: date, end: date) -> List[date]:
+    """Every calendar day from start to end inclusive"""
+    return [start + timedelta(days=i) for i in range((end - start).days + 1)]
+
+
+def fill_gaps(observations: Dict[date, float], start: date, end: date,
+              method: str = 'none', max_gap: int = 3) -> List[Dict]:
+    """
+    Expand observations into a daily series and fill missing days
+
+    Args:
+        observations: Observed values keyed by date
+        start: First day of the output series
+        end: Last day of the output series
+        method: 'interpolate' fills linearly between neighbours, 'previous' carries
+                the last observation forward, 'none' leaves gaps as nulls
+        max_gap: Longest run of missing days that will be filled; longer gaps stay null
+
+    Returns:
+        One dict per day with 'date', 'value' and 'filled' keys
+    """
+    if method not in FILL_METHODS:
+        raise ValueError(f"Unknown fill method: {method}")
+
+    days = daily_range(start, end)
+    series = [{'date': day, 'value': observations.get(day), 'filled': False} for day in days]
+    if method == 'none':
+        return series
+
+    i = 0
+    while i < len(series):
+        if series[i]['value'] is not None:
+            i += 1
+            continue
+
+        # Find the run of missing days [i, j)
+        j = i
+        while j < len(series) and series[j]['value'] is None:
+            j += 1
+        gap = j - i
+        before = series[i - 1]['value'] if i > 0 else None
+        after = series[j]['value'] if j < len(series) else None
+
+        if gap <= max_gap and before is not None:
+            for k in range(i, j):
+                if method == 'previous':
+                    value = before
+                elif after is not None:
+                    value = before + (after - before) * (k - i + 1) / (gap + 1)
+                else:
+                    # Trailing gap: nothing to interpolate towards
+                    continue
+                series[k]['value'] = value
+                series[k]['filled'] = True
+        i = j
+
+    return series
+
+
+def _least_squares(xs: List[float], ys: List[float]) -> Optional[tuple]:
+    """Slope and intercept of the least-squares line through (xs, ys)"""
+    n = len(xs)
+    mean_x = sum(xs) / n
+    mean_y = sum(ys) / n
+    sxx = sum((x - mean_x) ** 2 for x in xs)
+    if sxx == 0:
+        return None
+    slope = sum((x - mean_x) * (y - mean_y) for x, y in zip(xs, ys)) / sxx
+    return slope, mean_y - slope * mean_x
+
+
+def linear_trend(observations: Dict[date, float]) -> Optional[Dict]:
+    """
+    Fit a least-squares line to observations against time
+
+    Returns:
+        Dict with 'slope' (value units per day), 'r_squared' and 'observations',
+        or None when fewer than 3 observations are available
+    """
+    if len(observations) < 3:
+        return None
+
+    first = min(observations)
+    xs = [(day - first).days for day in observations]
+    ys = list(observations.values())
+    fit = _least_squares(xs, ys)
+    if fit is None:
+        return None
+    slope, intercept = fit
+
+    mean_y = sum(ys) / len(ys)
+    ss_tot = sum((y - mean_y) ** 2 for y in ys)
+    ss_res = sum((y - (intercept + slope * x)) ** 2 for x, y in zip(xs, ys))
+    # A flat series is fitted exactly by a flat line
+    r_squared = 1.0 if min(ys) == max(ys) else 1 - ss_res / ss_tot
+
+    return {'slope': slope, 'r_squared': r_squared, 'observations': len(xs)}
+
+
+def correlation(xs: List[float], ys: List[float]) -> Optional[float]:
+    """Pearson correlation of two equal-length series, None when undefined"""
+    n = len(xs)
+    if n < 2 or n != len(ys):
+        return None
+    # Check constancy directly; variances of constant floats carry rounding noise
+    if min(xs) == max(xs) or min(ys) == max(ys):
+        return None
+    mean_x = sum(xs) / n
+    mean_y = sum(ys) / n
+    sxy = sum((x - mean_x) * (y - mean_y) for x, y in zip(xs, ys))
+    sxx = sum((x - mean_x) ** 2 for x in xs)
+    syy = sum((y - mean_y) ** 2 for y in ys)
+    return sxy / (sxx * syy) ** 0.5
+
+
+def drydown_forecast(observations: Dict[date, float], horizon: int,
+                     min_observations: int = 10) -> Optional[Dict]:
+    """
+    Forecast moisture as exponential decay toward a dry-down asymptote
+
+    The asymptote and decay rate are chosen by a grid search over asymptotes below the
+    driest observation, fitting the log of the excess moisture for each and keeping the
+    best fit. Uncertainty grows with the square root of lead time, scaled by the
+    observed day-to-day variability.
+
+    Returns:
+        Dict with 'asymptote', 'decay_rate' (per day) and 'forecast' points carrying
+        'date', 'value', 'lower' and 'upper', or None when history is too sparse
+    """
+    if len(observations) < min_observations:
+        return None
+
+    days = sorted(observations)
+    first, last_day = days[0], days[-1]
+    last_value = observations[last_day]
+    xs = [(day - first).days for day in days]
+    ys = [observations[day] for day in days]
+
+    # Without a dry-down to extrapolate (flat or wetting history) persist the last value
+    asymptote, decay_rate, best_error = min(ys), 0.0, None
+    for step in range(50 if min(ys) > 0 else 0):
+        candidate = min(ys) * step / 50
+        fit = _least_squares(xs, [math.log(y - candidate) for y in ys])
+        if fit is None or fit[0] >= 0:
+            continue
+        slope, intercept = fit
+        error = sum((y - candidate - math.exp(intercept + slope * x)) ** 2 for x, y in zip(xs, ys))
+        if best_error is None or error < best_error:
+            asymptote, decay_rate, best_error = candidate, -slope, error
+
+    changes = [(observations[b] - observations[a]) / (b - a).days for a, b in zip(days, days[1:])]
+    mean_change = sum(changes) / len(changes)
+    daily_sd = math.sqrt(sum((c - mean_change) ** 2 for c in changes) / len(changes))
+
+    forecast = []
+    for lead in range(1, horizon + 1):
+        value = asymptote + (last_value - asymptote) * math.exp(-decay_rate * lead)
+        spread = 1.96 * daily_sd * math.sqrt(lead)
+        forecast.append({
+            'date': last_day + timedelta(days=lead),
+            'value': value,
+            'lower': max(0.0, value - spread),
+            'upper': value + spread
+        })
+
+    return {'asymptote': asymptote, 'decay_rate': decay_rate, 'forecast': forecast}
+
+
+def last_recharge(observations: Dict[date, float], delta: float, max_lag: int = 2) -> Optional[Dict]:
+    """
+    Find the most recent recharge: a rise of more than delta between two observations
+    at most max_lag days apart (24-48 hours by default, as retrievals skip days)
+
+    Returns:
+        Dict with 'date' (the day the wetter value was observed), 'before' and 'increase',
+        or None when no rise in the series is large enough
+    """
+    days = sorted(observations)
+    for i in range(len(days) - 1, 0, -1):
+        later = days[i]
+        # Only a day that is itself a step up dates the rise, not the drying days after its peak
+        if observations[later] <= observations[days[i - 1]]:
+            continue
+        earlier = [day for day in days[max(0, i - max_lag):i] if (later - day).days <= max_lag]
+        # Measure from the driest recent day, so a rise spread over two days still counts
+        before = min(observations[day] for day in earlier) if earlier else None
+        if before is not None and observations[later] - before > delta:
+            return {'date': later, 'before': before, 'increase': observations[later] - before}
+    return None
+
+
+def water_balance(moisture: Dict[date, float], precipitation: Dict[date, float], start: date, end: date,
+                  depth_mm: float) -> List[Dict]:
+    """
+    Align moisture and precipitation into a daily series with a naive running balance
+
+    The balance is precipitation since start minus the change in water stored in the
+    sensed layer (moisture times depth_mm) since the first observation: roughly what
+    evapotranspiration, drainage and runoff took. It is only defined on days with a
+    moisture observation; days without a precipitation value count as dry.
+
+    Returns:
+        One dict per day with 'date', 'moisture', 'precipitation' and 'balance' keys
+    """
+    series = []
+    rainfall = 0.0
+    first = None
+    for day in daily_range(start, end):
+        value = moisture.get(day)
+        rainfall += precipitation.get(day) or 0.0
+        balance = None
+        if value is not None:
+            if first is None:
+                # Rain before the first observation is already reflected in it
+                first, rainfall = value, 0.0
+            balance = rainfall - (value - first) * depth_mm
+        series.append({'date': day, 'moisture': value, 'precipitation': precipitation.get(day),
+                       'balance': balance})
+    return series
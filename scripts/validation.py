@@ -0,0 +1,73 @@
+import json
+import math
+from typing import Any, Dict, Optional
+
+"""
+Request body validation errors that locate the failing value with an RFC 6901 JSON
+Pointer (e.g. /days/3/tmax), alongside the expected type and a snippet of what was sent.
+"""
+
+SNIPPET_LENGTH = 60
+_MISSING = object()
+
+
+def pointer(*tokens) -> str:
+    """JSON Pointer for a path of object keys and array indexes; '' is the whole document"""
+    return ''.join('/' + str(token).replace('~', '~0').replace('/', '~1') for token in tokens)
+
+
+def json_type(value: Any) -> str:
+    """JSON type name of a decoded value"""
+    if value is None:
+        return 'null'
+    if isinstance(value, bool):
+        return 'boolean'
+    if isinstance(value, (int, float)):
+        return 'number'
+    if isinstance(value, str):
+        return 'string'
+    return 'array' if isinstance(value, list) else 'object'
+
+
+def snippet(value: Any) -> str:
+    """Compact JSON rendering of a value, truncated for error messages"""
+    text = json.dumps(value, separators=(',', ':'))
+    return text if len(text) <= SNIPPET_LENGTH else text[:SNIPPET_LENGTH - 3] + '...'
+
+
+class InvalidInput(ValueError):
+    """A request body value that failed validation"""
+
+    def __init__(self, message: str, at: str, expected: Optional[str] = None, received: Any = _MISSING):
+        super().__init__(message)
+        self.pointer = at
+        self.expected = expected
+        self.received = received
+
+    def to_dict(self) -> Dict:
+        details = {'error': str(self), 'pointer': self.pointer}
+        if self.expected:
+            details['expected'] = self.expected
+        if self.received is not _MISSING:
+            details['received'] = snippet(self.received)
+        return details
+
+
+def expect(value: Any, expected: str, at: str) -> Any:
+    """Return value if its JSON type is expected ('number' excludes booleans and non-finite values), else raise"""
+    if json_type(value) != expected:
+        raise InvalidInput(f"expected {expected}, got {json_type(value)}", at, expected, value)
+    # json.loads accepts NaN and Infinity, and 1e999 overflows to infinity; none are JSON numbers
+    if expected == 'number' and not math.isfinite(value):
+        raise InvalidInput("expected number, got a non-finite value", at, expected, value)
+    return value
+
+
+def decode(raw: bytes) -> Any:
+    """Parse a JSON body, reporting syntax errors by line and column"""
+    try:
+        return json.loads(raw)
+    except json.JSONDecodeError as e:
+        raise InvalidInput(f"body is not valid JSON: {e.msg} at line {e.lineno} column {e.colno}", '') from None
+    except UnicodeDecodeError:
+        raise InvalidInput("body is not valid UTF-8", '') from None